@@ -0,0 +1,199 @@
+//! Latency of `queries/get_quotes.sql` against a seeded database.
+//!
+//! `QUOTEFAULT_BENCH_DATABASE_URL=postgres://... cargo bench --bench get_quotes`
+//!
+//! The database is migrated and, if it has no quotes yet, seeded with 100k
+//! quotes from `benches/seed.sql`. Point it at a scratch database.
+//! `QUOTEFAULT_BENCH_ITERATIONS` overrides the number of runs per scenario.
+
+use std::{
+    env,
+    time::{Duration, Instant},
+};
+
+use quotefault_backend::schema::db::{QuoteShard, Vote};
+use sqlx::{postgres::PgPoolOptions, query_file_as, Executor, Pool, Postgres};
+
+const WARMUP: usize = 5;
+const ITERATIONS: usize = 50;
+const USERNAME: &str = "u1";
+
+struct Scenario {
+    name: &'static str,
+    sort: &'static str,
+    q: Option<&'static str>,
+    speaker: &'static [&'static str],
+    involved: &'static [&'static str],
+    hidden: Option<bool>,
+    favorited: bool,
+    moderator: bool,
+}
+
+const DEFAULT: Scenario = Scenario {
+    name: "",
+    sort: "date",
+    q: None,
+    speaker: &[],
+    involved: &[],
+    hidden: None,
+    favorited: false,
+    moderator: false,
+};
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        name: "newest",
+        ..DEFAULT
+    },
+    Scenario {
+        name: "sort=votes",
+        sort: "votes",
+        ..DEFAULT
+    },
+    Scenario {
+        name: "sort=hot",
+        sort: "hot",
+        ..DEFAULT
+    },
+    Scenario {
+        name: "sort=controversial",
+        sort: "controversial",
+        ..DEFAULT
+    },
+    Scenario {
+        name: "q=pizza robot",
+        sort: "relevance",
+        q: Some("pizza robot"),
+        ..DEFAULT
+    },
+    Scenario {
+        name: "speaker=u2",
+        speaker: &["u2"],
+        ..DEFAULT
+    },
+    Scenario {
+        name: "involved=u2",
+        involved: &["u2"],
+        ..DEFAULT
+    },
+    Scenario {
+        name: "favorited=true",
+        favorited: true,
+        ..DEFAULT
+    },
+    Scenario {
+        name: "hidden=true (moderator)",
+        hidden: Some(true),
+        moderator: true,
+        ..DEFAULT
+    },
+];
+
+async fn list(db: &Pool<Postgres>, scenario: &Scenario) -> usize {
+    let speaker: Vec<String> = scenario.speaker.iter().map(|x| x.to_string()).collect();
+    let involved: Vec<String> = scenario.involved.iter().map(|x| x.to_string()).collect();
+    query_file_as!(
+        QuoteShard,
+        "queries/get_quotes.sql",
+        10_i64,                           // $1
+        0_i32,                            // $2
+        scenario.q,                       // $3
+        &speaker,                         // $4
+        &Vec::<String>::new(),            // $5
+        scenario.hidden.unwrap_or(false), // $6
+        scenario.hidden.is_some(),        // $7
+        USERNAME,                         // $8
+        scenario.moderator,               // $9
+        &involved,                        // $10
+        scenario.favorited,               // $11
+        scenario.sort,                    // $12
+        false,                            // $13
+        None::<String>,                   // $14
+        None::<i32>,                      // $15
+        false,                            // $16
+        None::<String>,                   // $17
+        None::<chrono::NaiveDateTime>,    // $18
+        None::<chrono::NaiveDateTime>,    // $19
+        None::<chrono::NaiveDateTime>,    // $20
+        None::<chrono::NaiveDateTime>,    // $21
+        false,                            // $22
+    )
+    .fetch_all(db)
+    .await
+    .expect("Failed to list quotes")
+    .len()
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> f64 {
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index].as_secs_f64() * 1000.0
+}
+
+#[actix_web::main]
+async fn main() {
+    let Ok(url) = env::var("QUOTEFAULT_BENCH_DATABASE_URL") else {
+        println!("QUOTEFAULT_BENCH_DATABASE_URL not set, skipping");
+        return;
+    };
+    let db = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .expect("Could not connect to database");
+    sqlx::migrate!("./migrations")
+        .run(&db)
+        .await
+        .expect("Failed to run migrations");
+
+    let quotes: i64 = sqlx::query_scalar("SELECT count(*) FROM quotes")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to count quotes");
+    if quotes == 0 {
+        let start = Instant::now();
+        db.execute(include_str!("seed.sql"))
+            .await
+            .expect("Failed to seed database");
+        println!("Seeded database in {:.1?}", start.elapsed());
+    }
+    let quotes: i64 = sqlx::query_scalar("SELECT count(*) FROM quotes")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to count quotes");
+    let votes: i64 = sqlx::query_scalar("SELECT count(*) FROM votes")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to count votes");
+    let iterations = env::var("QUOTEFAULT_BENCH_ITERATIONS")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(ITERATIONS);
+    println!("{quotes} quotes, {votes} votes, {iterations} runs each\n");
+
+    println!(
+        "{:<24} {:>9} {:>9} {:>9} {:>7}",
+        "scenario", "mean ms", "p50 ms", "p95 ms", "shards"
+    );
+    for scenario in SCENARIOS {
+        for _ in 0..WARMUP.min(iterations) {
+            list(&db, scenario).await;
+        }
+        let mut timings = Vec::with_capacity(iterations);
+        let mut shards = 0;
+        for _ in 0..iterations {
+            let start = Instant::now();
+            shards = list(&db, scenario).await;
+            timings.push(start.elapsed());
+        }
+        timings.sort();
+        let mean = timings.iter().sum::<Duration>().as_secs_f64() * 1000.0 / iterations as f64;
+        println!(
+            "{:<24} {:>9.2} {:>9.2} {:>9.2} {:>7}",
+            scenario.name,
+            mean,
+            percentile(&timings, 0.5),
+            percentile(&timings, 0.95),
+            shards
+        );
+    }
+}
@@ -0,0 +1,90 @@
+-- 100k quotes by 500 users with 1-3 shards, ~10 votes, some favorites,
+-- tags, hides and pending approvals each. Row triggers are disabled while
+-- seeding and their columns are filled in afterwards.
+SELECT setseed(0.42);
+
+ALTER TABLE public.shards DISABLE TRIGGER shards_update_quote_search;
+ALTER TABLE public.votes DISABLE TRIGGER votes_update_quote_votes;
+
+INSERT INTO public.quotes (submitter, "timestamp", said_at, context)
+SELECT
+  'u' || (1 + floor(random() * 500)),
+  now()::timestamp - random() * interval '5 years',
+  CASE WHEN random() < 0.3 THEN now()::timestamp - random() * interval '6 years' END,
+  CASE WHEN random() < 0.1 THEN 'During ' || md5(random()::text) END
+FROM generate_series(1, 100000);
+
+INSERT INTO public.shards (quote_id, index, body, speaker)
+SELECT
+  q.id,
+  i,
+  (
+    SELECT string_agg(
+      (ARRAY[
+        'house', 'meeting', 'coffee', 'server', 'project', 'elevator', 'pizza',
+        'printer', 'midnight', 'floor', 'vote', 'drink', 'code', 'bug', 'deploy',
+        'lounge', 'couch', 'laser', 'robot', 'snow'
+      ])[1 + floor(random() * 20)],
+      ' '
+    )
+    FROM generate_series(1, 4 + floor(random() * 12)::int + q.id * 0)
+  ),
+  'u' || (1 + floor(random() * 500))
+FROM public.quotes q
+CROSS JOIN LATERAL generate_series(1, 1 + floor(random() * 3)::int + q.id * 0) AS i;
+
+INSERT INTO public.votes (quote_id, vote, submitter, "timestamp")
+SELECT
+  q.id,
+  (CASE WHEN random() < 0.75 THEN 'upvote' ELSE 'downvote' END)::public.vote,
+  'u' || (1 + (q.id * 7 + i * 13) % 500),
+  q."timestamp" + random() * (now()::timestamp - q."timestamp")
+FROM public.quotes q
+CROSS JOIN LATERAL generate_series(1, floor(random() * 21)::int + q.id * 0) AS i;
+
+INSERT INTO public.favorites (quote_id, username)
+SELECT 1 + floor(random() * 100000), 'u' || (1 + floor(random() * 500))
+FROM generate_series(1, 100000)
+ON CONFLICT DO NOTHING;
+
+INSERT INTO public.quote_tags (quote_id, tag)
+SELECT
+  id,
+  (ARRAY['house', 'meeting', 'late-night', 'eboard', 'classic'])[1 + floor(random() * 5)]
+FROM public.quotes
+WHERE random() < 0.2
+ON CONFLICT DO NOTHING;
+
+INSERT INTO public.hide_events (quote_id, action, reason, actor)
+SELECT id, 'hide', 'Seeded hidden quote', 'u1'
+FROM public.quotes
+WHERE random() < 0.01;
+
+INSERT INTO public.pending_approvals (quote_id, speaker)
+SELECT DISTINCT s.quote_id, s.speaker
+FROM public.shards s
+WHERE s.quote_id % 200 = 0;
+
+UPDATE public.quotes q SET search = (
+  SELECT to_tsvector('english', coalesce(string_agg(body, ' ' ORDER BY index), ''))
+  FROM public.shards WHERE quote_id = q.id
+);
+
+UPDATE public.quotes q SET
+  upvotes = t.upvotes,
+  downvotes = t.downvotes,
+  hot = public.quote_hot(q.id)
+FROM (
+  SELECT
+    quote_id,
+    count(*) FILTER (WHERE vote = 'upvote') AS upvotes,
+    count(*) FILTER (WHERE vote = 'downvote') AS downvotes
+  FROM public.votes
+  GROUP BY quote_id
+) t
+WHERE t.quote_id = q.id;
+
+ALTER TABLE public.shards ENABLE TRIGGER shards_update_quote_search;
+ALTER TABLE public.votes ENABLE TRIGGER votes_update_quote_votes;
+
+ANALYZE;
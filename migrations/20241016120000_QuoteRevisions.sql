@@ -0,0 +1,14 @@
+-- Add migration script here
+ALTER TABLE public.quotes ADD COLUMN edited timestamp without time zone;
+
+CREATE TABLE public.revisions (
+  quote_id integer NOT NULL,
+  revision integer NOT NULL,
+  index smallint NOT NULL,
+  body text NOT NULL,
+  speaker character varying(32) NOT NULL,
+  editor character varying(32) NOT NULL,
+  "timestamp" timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  PRIMARY KEY (quote_id, revision, index),
+  CONSTRAINT fk_quote FOREIGN KEY(quote_id) REFERENCES public.quotes(id) ON DELETE CASCADE
+);
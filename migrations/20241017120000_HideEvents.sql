@@ -0,0 +1,34 @@
+-- Add migration script here
+CREATE TYPE public.hide_action AS ENUM (
+    'hide',
+    'unhide'
+);
+
+CREATE TABLE public.hide_events (
+  id integer GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  quote_id integer NOT NULL,
+  action public.hide_action NOT NULL,
+  reason text,
+  actor character varying(32) NOT NULL,
+  "timestamp" timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  CONSTRAINT hide_reason CHECK (action = 'unhide' OR reason IS NOT NULL),
+  CONSTRAINT fk_quote FOREIGN KEY(quote_id) REFERENCES public.quotes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX hide_events_quote_id_idx ON public.hide_events (quote_id, id);
+
+INSERT INTO public.hide_events(quote_id, action, reason, actor) (
+  SELECT quote_id, 'hide', reason, actor FROM public.hidden WHERE quote_id IS NOT NULL
+);
+
+DROP TABLE public.hidden;
+
+-- A quote is hidden while its most recent hide event is a hide
+CREATE VIEW public.hidden AS
+  SELECT quote_id, reason, actor, "timestamp"
+  FROM (
+    SELECT DISTINCT ON (quote_id) quote_id, action, reason, actor, "timestamp"
+    FROM public.hide_events
+    ORDER BY quote_id, id DESC
+  ) AS latest
+  WHERE action = 'hide';
@@ -0,0 +1,33 @@
+-- Add migration script here
+CREATE TYPE public.moderation_action AS ENUM (
+    'hide',
+    'unhide',
+    'resolve',
+    'edit',
+    'delete',
+    'view_hidden'
+);
+
+-- quote_id intentionally has no foreign key so entries outlive deleted quotes
+CREATE TABLE public.moderation_log (
+  id integer GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  actor character varying(32) NOT NULL,
+  action public.moderation_action NOT NULL,
+  quote_id integer,
+  details text,
+  "timestamp" timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX moderation_log_actor_idx ON public.moderation_log (actor);
+CREATE INDEX moderation_log_quote_id_idx ON public.moderation_log (quote_id);
+CREATE INDEX moderation_log_timestamp_idx ON public.moderation_log ("timestamp");
+
+CREATE FUNCTION public.moderation_log_append_only() RETURNS trigger AS $$
+BEGIN
+  RAISE EXCEPTION 'moderation_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER moderation_log_append_only
+  BEFORE UPDATE OR DELETE OR TRUNCATE ON public.moderation_log
+  FOR EACH STATEMENT EXECUTE FUNCTION public.moderation_log_append_only();
@@ -0,0 +1,30 @@
+-- Add migration script here
+ALTER TABLE public.quotes ADD COLUMN search tsvector DEFAULT ''::tsvector NOT NULL;
+
+CREATE FUNCTION public.update_quote_search() RETURNS trigger AS $$
+DECLARE
+  qid integer;
+BEGIN
+  IF TG_OP = 'DELETE' THEN
+    qid := OLD.quote_id;
+  ELSE
+    qid := NEW.quote_id;
+  END IF;
+  UPDATE public.quotes SET search = (
+    SELECT to_tsvector('english', coalesce(string_agg(body, ' ' ORDER BY index), ''))
+    FROM public.shards WHERE quote_id = qid
+  ) WHERE id = qid;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER shards_update_quote_search
+  AFTER INSERT OR UPDATE OR DELETE ON public.shards
+  FOR EACH ROW EXECUTE FUNCTION public.update_quote_search();
+
+UPDATE public.quotes q SET search = (
+  SELECT to_tsvector('english', coalesce(string_agg(body, ' ' ORDER BY index), ''))
+  FROM public.shards WHERE quote_id = q.id
+);
+
+CREATE INDEX quotes_search_idx ON public.quotes USING gin (search);
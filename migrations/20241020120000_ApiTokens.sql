@@ -0,0 +1,21 @@
+-- Add migration script here
+CREATE TYPE public.api_token_scope AS ENUM (
+    'read',
+    'write',
+    'moderate'
+);
+
+-- Only a SHA3-256 hash of each token is stored. The owner's role is resolved
+-- from their LDAP groups whenever the token is used.
+CREATE TABLE public.api_tokens (
+  id integer GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+  owner character varying(32) NOT NULL,
+  name character varying(64) NOT NULL,
+  token_hash bytea NOT NULL UNIQUE,
+  scope public.api_token_scope NOT NULL,
+  created timestamp without time zone DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  expires timestamp without time zone NOT NULL,
+  last_used timestamp without time zone
+);
+
+CREATE INDEX api_tokens_owner_idx ON public.api_tokens (owner);
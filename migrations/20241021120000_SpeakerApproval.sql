@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE public.user_settings (
+  username character varying(32) PRIMARY KEY,
+  require_approval boolean DEFAULT false NOT NULL
+);
+
+-- A quote is pending while any of its speakers has yet to approve it
+CREATE TABLE public.pending_approvals (
+  quote_id integer NOT NULL,
+  speaker character varying(32) NOT NULL,
+  PRIMARY KEY (quote_id, speaker),
+  CONSTRAINT fk_quote FOREIGN KEY(quote_id) REFERENCES public.quotes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX pending_approvals_speaker_idx ON public.pending_approvals (speaker);
@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE public.user_settings ADD COLUMN opt_out boolean DEFAULT false NOT NULL;
+
+ALTER TYPE public.moderation_action ADD VALUE 'opt_out';
@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE public.quote_tags (
+  quote_id integer NOT NULL,
+  tag character varying(32) NOT NULL,
+  PRIMARY KEY (quote_id, tag),
+  CONSTRAINT fk_quote FOREIGN KEY(quote_id) REFERENCES public.quotes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX quote_tags_tag_idx ON public.quote_tags (tag);
@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE public.quotes ADD COLUMN said_at timestamp without time zone;
+ALTER TABLE public.quotes ADD COLUMN context text;
+
+CREATE INDEX quotes_said_at_idx ON public.quotes (said_at);
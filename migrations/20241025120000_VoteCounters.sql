@@ -0,0 +1,85 @@
+-- Add migration script here
+ALTER TABLE public.quotes
+  ADD COLUMN upvotes integer DEFAULT 0 NOT NULL,
+  ADD COLUMN downvotes integer DEFAULT 0 NOT NULL,
+  ADD COLUMN score integer GENERATED ALWAYS AS (upvotes - downvotes) STORED,
+  ADD COLUMN hot numeric DEFAULT 0 NOT NULL;
+
+-- Highest for many upvotes and downvotes in balance
+ALTER TABLE public.quotes ADD COLUMN controversy numeric GENERATED ALWAYS AS (
+  CASE
+    WHEN upvotes > 0 AND downvotes > 0
+    THEN power(
+      (upvotes + downvotes)::numeric,
+      least(upvotes, downvotes)::numeric / greatest(upvotes, downvotes)
+    )
+    ELSE 0
+  END
+) STORED;
+
+-- Weighs each vote by 2^(weeks between 2024 and when it was cast), so a vote
+-- counts half as much as one cast a week later, and returns the signed base 2
+-- log of one plus the magnitude of the weighted sum. The weights are summed
+-- relative to the newest vote to stay within double precision, so `hot` only
+-- grows by about 52 a year.
+CREATE FUNCTION public.quote_hot(integer) RETURNS numeric AS $$
+  WITH weighted AS (
+    SELECT
+      CASE WHEN vote = 'upvote' THEN 1 ELSE -1 END AS direction,
+      extract(epoch FROM "timestamp" - '2024-01-01')::float8 / 604800 AS weeks
+    FROM public.votes
+    WHERE quote_id = $1
+  ), newest AS (
+    SELECT max(weeks) AS weeks FROM weighted
+  ), total AS (
+    SELECT newest.weeks, sum(direction * power(2, weighted.weeks - newest.weeks)) AS relative
+    FROM weighted, newest
+    GROUP BY newest.weeks
+  )
+  SELECT coalesce(
+    (SELECT (sign(relative) * (weeks + ln(power(2, -weeks) + abs(relative)) / ln(2)))::numeric FROM total),
+    0
+  );
+$$ LANGUAGE sql STABLE;
+
+CREATE FUNCTION public.update_quote_votes() RETURNS trigger AS $$
+BEGIN
+  IF TG_OP <> 'INSERT' THEN
+    UPDATE public.quotes SET
+      upvotes = upvotes - (OLD.vote = 'upvote')::integer,
+      downvotes = downvotes - (OLD.vote = 'downvote')::integer,
+      hot = public.quote_hot(OLD.quote_id)
+    WHERE id = OLD.quote_id;
+  END IF;
+  IF TG_OP <> 'DELETE' THEN
+    UPDATE public.quotes SET
+      upvotes = upvotes + (NEW.vote = 'upvote')::integer,
+      downvotes = downvotes + (NEW.vote = 'downvote')::integer,
+      hot = public.quote_hot(NEW.quote_id)
+    WHERE id = NEW.quote_id;
+  END IF;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER votes_update_quote_votes
+  AFTER INSERT OR UPDATE OR DELETE ON public.votes
+  FOR EACH ROW EXECUTE FUNCTION public.update_quote_votes();
+
+UPDATE public.quotes q SET
+  upvotes = t.upvotes,
+  downvotes = t.downvotes,
+  hot = public.quote_hot(q.id)
+FROM (
+  SELECT
+    quote_id,
+    count(*) FILTER (WHERE vote = 'upvote') AS upvotes,
+    count(*) FILTER (WHERE vote = 'downvote') AS downvotes
+  FROM public.votes
+  GROUP BY quote_id
+) t
+WHERE t.quote_id = q.id;
+
+CREATE INDEX shards_speaker_idx ON public.shards (speaker);
+CREATE INDEX votes_submitter_idx ON public.votes (submitter);
+CREATE INDEX favorites_username_idx ON public.favorites (username);
@@ -0,0 +1,31 @@
+-- Add migration script here
+-- Whether someone can see a quote. Hidden quotes are only visible to
+-- moderators and the people involved in them, and pending quotes only to the
+-- people involved in them. Takes plain flags and uses each once so it is
+-- inlined into listings, checking involvement, the costliest, last.
+CREATE FUNCTION public.quote_visible(
+  hidden boolean,
+  pending boolean,
+  involved boolean,
+  is_moderator boolean
+) RETURNS boolean AS $$
+  SELECT (NOT pending AND (is_moderator OR NOT hidden)) OR involved;
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Whether `username` can see the quote `quote_id`
+CREATE FUNCTION public.quote_visible(
+  quote_id integer,
+  username character varying,
+  is_moderator boolean
+) RETURNS boolean AS $$
+  SELECT public.quote_visible(
+    quote_id IN (SELECT h.quote_id FROM public.hidden h),
+    quote_id IN (SELECT p.quote_id FROM public.pending_approvals p),
+    username IN (
+      SELECT submitter FROM public.quotes WHERE id = quote_visible.quote_id
+      UNION ALL
+      SELECT speaker FROM public.shards s WHERE s.quote_id = quote_visible.quote_id
+    ),
+    is_moderator
+  );
+$$ LANGUAGE sql STABLE;
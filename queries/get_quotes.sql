@@ -3,13 +3,25 @@ select
     s.index as "index!",
     pq.submitter as "submitter!",
     pq.timestamp as "timestamp!",
+    pq.edited as "edited",
+    pq.said_at as "said_at",
+    pq.context as "context",
     s.body as "body!",
     s.speaker as "speaker!",
-    hidden.reason as "hidden_reason: Option<String>",
-    hidden.actor as "hidden_actor: Option<String>",
+    hidden.reason as "hidden_reason",
+    hidden.actor as "hidden_actor",
+    hidden.timestamp as "hidden_timestamp",
     v.vote as "vote: Option<Vote>",
-    (case when pq.score is null then 0 else pq.score end) as "score!",
-    (case when f.username is null then false else true end) as "favorited!"
+    pq.score::int8 as "score!",
+    pq.upvotes::int8 as "upvotes!",
+    pq.downvotes::int8 as "downvotes!",
+    pq.relevance as "relevance",
+    pq.sort_key::text as "sort_key",
+    (case when f.username is null then false else true end) as "favorited!",
+    pq.pending as "pending!",
+    array(
+        select tag from quote_tags where quote_id = pq.id order by tag
+    ) as "tags!"
 from
     (
         select *
@@ -19,88 +31,126 @@ from
                     id,
                     submitter,
                     timestamp,
-                    (case when quote_id is not null then true else false end) as hidden
+                    edited,
+                    said_at,
+                    context,
+                    upvotes,
+                    downvotes,
+                    score,
+                    hot,
+                    controversy,
+                    (case when quote_id is not null then true else false end) as hidden,
+                    id in (select quote_id from pending_approvals) as pending,
+                    (
+                        submitter = $8
+                        or id in (select quote_id from shards where speaker = $8)
+                    ) as involves_user,
+                    (
+                        case
+                            when $3::text is not null
+                            then ts_rank(search, websearch_to_tsquery('english', $3::text))
+                        end
+                    ) as relevance
                 from quotes as _q
                 left join (select quote_id from hidden) _h on _q.id = _h.quote_id
             ) as q
-        left join
+        cross join lateral
             (
                 select
-                    quote_id,
-                    sum(
+                    (case when $13::bool then 1 else -1 end) * (
                         case
-                            when vote = 'upvote'
-                            then 1
-                            when vote = 'downvote'
-                            then -1
-                            else 0
+                            $12::text
+                            when 'votes'
+                            then q.score::numeric
+                            when 'hot'
+                            then q.hot
+                            when 'controversial'
+                            then q.controversy
+                            when 'relevance'
+                            then coalesce(q.relevance, 0)::numeric
+                            when 'said_at'
+                            then extract(epoch from coalesce(q.said_at, q.timestamp))
+                            when 'favorites'
+                            then (select count(*) from favorites where quote_id = q.id)::numeric
+                            when 'random'
+                            then random()::numeric
+                            else extract(epoch from q.timestamp)
                         end
-                    ) as score
-                from votes
-                group by quote_id
-            ) as t
-            on t.quote_id = q.id
+                    ) as sort_key
+            ) as k
         where
-            case
-                when $7 and $6 and $9
-                then q.hidden
-                when $7 and $6
-                then
-                    case
-                        when
-                            (
-                                q.submitter = $8
-                                or $8
-                                in (select speaker from shards where quote_id = q.id)
-                            )
-                        then q.hidden
-                        else false
-                    end
-                when $7 and not $6
-                then not q.hidden
-                else
-                    (
-                        case
-                            when
-                                q.hidden
-                                and (
-                                    q.submitter = $8
-                                    or $8 in (
-                                        select speaker from shards where quote_id = q.id
-                                    )
-                                )
-                            then q.hidden
-                            else not q.hidden
-                        end
-                    )
+            quote_visible(q.hidden, q.pending, q.involves_user, $9)
+            and case
+                when $7 then q.hidden = $6 else not q.hidden or q.involves_user
+            end
+            and case
+                when $16
+                then q.id in (select quote_id from pending_approvals where speaker = $8)
+                else true
             end
+            and (
+                $17::text is null
+                or q.id in (select quote_id from quote_tags where tag = $17::text)
+            )
+            and ($18::timestamp is null or q.said_at >= $18::timestamp)
+            and ($19::timestamp is null or q.said_at < $19::timestamp)
+            and ($20::timestamp is null or q.timestamp >= $20::timestamp)
+            and ($21::timestamp is null or q.timestamp < $21::timestamp)
             and case when $2::int4 > 0 then q.id < $2::int4 else true end
-            and submitter like $5
             and (
-                submitter like $10
-                or q.id in (select quote_id from shards s where speaker like $10)
+                $14::text::numeric is null
+                or k.sort_key > $14::text::numeric
+                or (k.sort_key = $14::text::numeric and q.id < $15::int4)
+            )
+            and (cardinality($5::varchar[]) = 0 or q.submitter = any($5::varchar[]))
+            and (
+                cardinality($10::varchar[]) = 0
+                or (
+                    q.submitter = any($10::varchar[])
+                    or q.id in (select quote_id from shards where speaker = any($10::varchar[]))
+                )
+                and (
+                    not $22::bool
+                    or (
+                        select count(distinct username)
+                        from
+                            (
+                                select q.submitter as username
+                                union all
+                                select speaker from shards where quote_id = q.id
+                            ) as involved
+                        where username = any($10::varchar[])
+                    )
+                    = cardinality($10::varchar[])
+                )
+            )
+            and (
+                $3::text is null
+                or q.id in (
+                    select id
+                    from quotes
+                    where search @@ websearch_to_tsquery('english', $3::text)
+                )
+            )
+            and (
+                cardinality($4::varchar[]) = 0
+                or q.id in (select quote_id from shards where speaker = any($4::varchar[]))
+                and (
+                    not $22::bool
+                    or (
+                        select count(distinct speaker)
+                        from shards
+                        where quote_id = q.id and speaker = any($4::varchar[])
+                    )
+                    = cardinality($4::varchar[])
+                )
             )
-            and q.id
-            in (select quote_id from shards where body ilike $3 and speaker like $4)
             and case
                 when $11
                 then q.id in (select quote_id from favorites where username = $8)
                 else true
             end
-        order by
-            (
-                case
-                    when $12::bool and $13::bool
-                    then score
-                    when $12::bool and not $13::bool
-                    then -1 * score
-                    when not $12::bool and $13::bool
-                    then extract(epoch from timestamp)
-                    when not $12::bool and not $13::bool
-                    then -1 * extract(epoch from timestamp)
-                end
-            ),
-            q.id desc
+        order by k.sort_key, q.id desc
         limit $1
     ) as pq
 left join hidden on hidden.quote_id = pq.id
@@ -110,18 +160,4 @@ left join
 left join
     (select quote_id, username from favorites where username = $8) f
     on f.quote_id = pq.id
-order by
-    (
-        case
-            when $12::bool and $13::bool
-            then score
-            when $12::bool and not $13::bool
-            then -1 * score
-            when not $12::bool and $13::bool
-            then extract(epoch from timestamp)
-            when not $12::bool and not $13::bool
-            then -1 * extract(epoch from timestamp)
-        end
-    ),
-    pq.id,
-    s.index
+order by pq.sort_key, pq.id desc, s.index
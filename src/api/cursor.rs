@@ -0,0 +1,135 @@
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::schema::api::FetchParams;
+
+const MAX_KEY_LENGTH: usize = 1000;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct QuoteCursor {
+    pub sort: String,
+    pub ascending: bool,
+    // Text form of the numeric sort key from get_quotes.sql so it round trips exactly
+    pub key: String,
+    pub id: i32,
+    // Hash of the filters the cursor was made for, which later pages must keep
+    #[serde(default)]
+    pub filters: String,
+}
+
+/// Hashes every parameter of a listing besides the sort, limit and cursor
+pub fn filter_hash(params: &FetchParams) -> String {
+    let filters = format!(
+        "{:?}",
+        (
+            (
+                &params.q,
+                params.lt,
+                &params.submitter,
+                &params.speaker,
+                &params.involved,
+                params.match_mode,
+                params.hidden,
+            ),
+            (
+                params.favorited,
+                params.pending,
+                &params.tag,
+                params.said_after,
+                params.said_before,
+                params.after,
+                params.before,
+            ),
+        )
+    );
+    general_purpose::URL_SAFE_NO_PAD.encode(&Sha3_256::digest(filters.as_bytes())[..12])
+}
+
+impl QuoteCursor {
+    pub fn encode(&self) -> Result<String> {
+        Ok(general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(self)?))
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        Ok(serde_json::from_slice(
+            &general_purpose::URL_SAFE_NO_PAD.decode(token)?,
+        )?)
+    }
+
+    /// Whether `key` is a plain decimal like Postgres prints numerics, and not
+    /// `NaN`, an infinity or an exponent that the query can't compare
+    pub fn has_valid_key(&self) -> bool {
+        let digits = self.key.strip_prefix('-').unwrap_or(&self.key);
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, "0"));
+        self.key.len() <= MAX_KEY_LENGTH
+            && !whole.is_empty()
+            && !fraction.is_empty()
+            && whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::params::parse_fetch_params;
+
+    fn cursor(key: &str) -> QuoteCursor {
+        QuoteCursor {
+            sort: "date".to_string(),
+            ascending: false,
+            key: key.to_string(),
+            id: 1,
+            filters: String::new(),
+        }
+    }
+
+    #[test]
+    fn only_accepts_plain_decimal_keys() {
+        for key in ["0", "-1698184988.254364", "12.5", "-3"] {
+            assert!(cursor(key).has_valid_key(), "{key}");
+        }
+        for key in [
+            "",
+            "-",
+            "NaN",
+            "inf",
+            "-Infinity",
+            "1e400",
+            "1.",
+            ".5",
+            "1.2.3",
+            "+1",
+            " 1",
+            "0x10",
+        ] {
+            assert!(!cursor(key).has_valid_key(), "{key}");
+        }
+        assert!(!cursor(&"9".repeat(MAX_KEY_LENGTH + 1)).has_valid_key());
+    }
+
+    #[test]
+    fn filter_hash_ignores_only_sort_limit_and_cursor() {
+        let hash = |query: &str| filter_hash(&parse_fetch_params(query).unwrap());
+        let base = hash("speaker=cole&tag=eboard");
+        assert_eq!(
+            hash("speaker=cole&tag=eboard&sort=votes&limit=5&cursor=abc"),
+            base
+        );
+        for query in [
+            "speaker=cole",
+            "speaker=cole&tag=rtp",
+            "speaker=cole&tag=eboard&q=pizza",
+            "speaker=cole&speaker=mcdade&tag=eboard",
+            "speaker=cole&tag=eboard&match=all",
+            "speaker=cole&tag=eboard&said_after=2024-01-01T00:00:00",
+            "speaker=cole&tag=eboard&favorited=true",
+        ] {
+            assert_ne!(hash(query), base, "{query}");
+        }
+    }
+}
@@ -1,20 +1,21 @@
-use actix_web::HttpResponse;
 use log::{log, Level};
-use sqlx::{postgres::PgQueryResult, Error, Pool, Postgres, Transaction};
+use sqlx::{postgres::PgQueryResult, query, Error, Executor, Pool, Postgres, Transaction};
 
-pub async fn open_transaction(db: &Pool<Postgres>) -> Result<Transaction<Postgres>, HttpResponse> {
+use crate::{api::error::ApiError, schema::db::ModerationAction};
+
+pub async fn open_transaction(db: &Pool<Postgres>) -> Result<Transaction<'_, Postgres>, ApiError> {
     match db.begin().await {
         Ok(t) => Ok(t),
         Err(e) => {
             log!(Level::Error, "Failed to open transaction: {}", e);
-            Err(HttpResponse::InternalServerError().body(format!("Internal DB Error: {}", e)))
+            Err(ApiError::internal())
         }
     }
 }
 
 pub async fn try_open_transaction(
     db: &Pool<Postgres>,
-) -> Result<Transaction<Postgres>, HttpResponse> {
+) -> Result<Transaction<'_, Postgres>, ApiError> {
     match db.try_begin().await {
         Ok(Some(t)) => Ok(t),
         Ok(None) => {
@@ -22,11 +23,11 @@ pub async fn try_open_transaction(
                 Level::Error,
                 "Failed to open transaction: Ok(None) transaction"
             );
-            Err(HttpResponse::InternalServerError().body("Internal DB Error: Ok(None) transaction"))
+            Err(ApiError::internal())
         }
         Err(e) => {
             log!(Level::Error, "Failed to open transaction: {}", e);
-            Err(HttpResponse::InternalServerError().body(format!("Internal DB Error: {}", e)))
+            Err(ApiError::internal())
         }
     }
 }
@@ -34,7 +35,7 @@ pub async fn try_open_transaction(
 pub async fn log_query_as<T>(
     query: Result<Vec<T>, Error>,
     tx: Option<Transaction<'_, Postgres>>,
-) -> Result<(Option<Transaction<'_, Postgres>>, Vec<T>), HttpResponse> {
+) -> Result<(Option<Transaction<'_, Postgres>>, Vec<T>), ApiError> {
     match query {
         Ok(v) => Ok((tx, v)),
         Err(e) => {
@@ -44,11 +45,11 @@ pub async fn log_query_as<T>(
                     Ok(_) => {}
                     Err(tx_e) => {
                         log!(Level::Error, "Transaction failed to rollback: {}", tx_e);
-                        return Err(HttpResponse::InternalServerError().body("Internal DB Error"));
+                        return Err(ApiError::internal());
                     }
                 }
             }
-            Err(HttpResponse::InternalServerError().body("Internal DB Error"))
+            Err(ApiError::internal())
         }
     }
 }
@@ -56,7 +57,7 @@ pub async fn log_query_as<T>(
 pub async fn log_query(
     query: Result<PgQueryResult, Error>,
     tx: Option<Transaction<'_, Postgres>>,
-) -> Result<(Option<Transaction<'_, Postgres>>, PgQueryResult), HttpResponse> {
+) -> Result<(Option<Transaction<'_, Postgres>>, PgQueryResult), ApiError> {
     match query {
         Ok(result) => Ok((tx, result)),
         Err(e) => {
@@ -66,11 +67,33 @@ pub async fn log_query(
                     Ok(_) => (),
                     Err(tx_e) => {
                         log!(Level::Error, "Transaction failed to rollback: {}", tx_e);
-                        return Err(HttpResponse::InternalServerError().body("Internal DB Error"));
+                        return Err(ApiError::internal());
                     }
                 }
             }
-            Err(HttpResponse::InternalServerError().body(format!("Internal DB Error: {e}")))
+            Err(ApiError::internal())
         }
     }
 }
+
+pub async fn log_moderation<'c, E>(
+    executor: E,
+    actor: &str,
+    action: ModerationAction,
+    quote_id: Option<i32>,
+    details: Option<String>,
+) -> Result<PgQueryResult, Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    query!(
+        "INSERT INTO moderation_log (actor, action, quote_id, details)
+        VALUES ($1, $2, $3, $4)",
+        actor,
+        action as ModerationAction,
+        quote_id,
+        details,
+    )
+    .execute(executor)
+    .await
+}
@@ -0,0 +1,46 @@
+use actix_web::{get, post, web::Json, HttpResponse};
+use log::{log, Level};
+
+use crate::{
+    api::error::ApiError,
+    auth::DEV_KEY,
+    schema::api::{DevTokenRequest, DevTokenResponse, ErrorCode},
+    utils::is_valid_username,
+};
+
+const DEFAULT_TTL: i64 = 12 * 60 * 60;
+
+#[get("/dev/jwks")]
+pub async fn get_dev_jwks() -> Result<HttpResponse, ApiError> {
+    let key = DEV_KEY.as_ref().ok_or_else(ApiError::forbidden)?;
+    match key.jwks() {
+        Ok(jwks) => Ok(HttpResponse::Ok().json(jwks)),
+        Err(err) => {
+            log!(Level::Error, "Failed to export dev JWKS: {err}");
+            Err(ApiError::internal())
+        }
+    }
+}
+
+#[post("/dev/token")]
+pub async fn mint_dev_token(Json(body): Json<DevTokenRequest>) -> Result<HttpResponse, ApiError> {
+    let key = DEV_KEY.as_ref().ok_or_else(ApiError::forbidden)?;
+    if !is_valid_username(&body.username) {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidUsername,
+            "Invalid username format specified.",
+        ));
+    }
+
+    let ttl = body.ttl.unwrap_or(DEFAULT_TTL).clamp(1, 30 * 24 * 60 * 60);
+    match key.mint(&body.username, body.groups, body.name, ttl) {
+        Ok((token, expires)) => {
+            log!(Level::Info, "Minted dev token for {}", body.username);
+            Ok(HttpResponse::Ok().json(DevTokenResponse { token, expires }))
+        }
+        Err(err) => {
+            log!(Level::Error, "Failed to mint dev token: {err}");
+            Err(ApiError::internal())
+        }
+    }
+}
@@ -1,41 +1,64 @@
-use std::collections::{BTreeSet, HashMap};
-use std::fmt::{self, Display};
+use std::{
+    collections::{BTreeSet, HashMap},
+    env,
+};
 
-use actix_web::body::MessageBody;
 use actix_web::{
-    delete, get,
-    http::StatusCode,
-    post, put,
+    delete, get, post, put,
     web::{self, Data, Json, Path},
-    HttpResponse, Responder, ResponseError,
+    HttpResponse, Responder,
 };
+use chrono::Datelike;
+use lazy_static::lazy_static;
 use log::{log, Level};
+use serde_json::json;
 use sha3::{Digest, Sha3_256};
-use sqlx::{query, query_as, query_file_as, Connection, Postgres, Transaction};
+use sqlx::{query, query_as, query_file_as, Connection, Pool, Postgres, Transaction};
 
 use crate::{
     api::{
-        db::{log_query, log_query_as, open_transaction},
+        cursor::{filter_hash, QuoteCursor},
+        db::{log_moderation, log_query, log_query_as, open_transaction},
+        error::ApiError,
         pings::send_ping,
     },
     app::AppState,
-    auth::{CSHAuth, User, SECURITY_ENABLED},
+    auth::{roles::Role, CSHAuth, User, SECURITY_ENABLED},
     ldap,
     schema::{
         api::{
-            FetchParams, Hidden, NewQuote, QuoteResponse, QuoteShardResponse, Reason,
-            ReportResponse, ReportedQuoteResponse, ResolveParams, UserResponse, VersionResponse,
-            VoteParams,
+            ErrorCode, FavoritedQuoteResponse, FetchParams, Hidden, HideEventResponse,
+            LeaderboardParams, LeaderboardResponse, LeaderboardWindow, MatchMode,
+            ModerationLogParams, NewQuote, NewQuoteShard, QuoteResponse, QuoteRevisionResponse,
+            QuoteShardResponse, QuotesResponse, Reason, ReportResponse, ReportedQuoteResponse,
+            ResolveParams, Tags, UserCountResponse, UserResponse, UserStatsResponse,
+            VersionResponse, VoteParams,
+        },
+        db::{
+            HideAction, HideEvent, ModerationAction, ModerationLogEntry, QuoteShard, QuoteVote,
+            ReportedQuoteShard, RevisionShard, TagCount, UserCount, Vote, ID,
         },
-        db::{QuoteShard, ReportedQuoteShard, Vote, ID},
     },
-    utils::is_valid_username,
+    utils::{is_valid_tag, is_valid_username},
 };
 
+const MAX_TAGS: usize = 10;
+const MAX_CONTEXT_LENGTH: usize = 500;
+const MAX_LEADERBOARD_LIMIT: i64 = 50;
+const REJECT_REASON: &str = "Rejected by a speaker";
+
+lazy_static! {
+    /// Time zone whose calendar days the quote of the day follows
+    static ref DAILY_TIMEZONE: String = env::var("QUOTEFAULT_TIMEZONE")
+        .ok()
+        .filter(|x| !x.is_empty())
+        .unwrap_or("America/New_York".to_string());
+}
+
 async fn shards_to_quotes(
     shards: &[QuoteShard],
     ldap: &ldap::client::LdapClient,
-) -> Result<Vec<QuoteResponse>, HttpResponse> {
+) -> Result<Vec<QuoteResponse>, ApiError> {
     let mut uid_map: HashMap<String, Option<String>> = HashMap::new();
     shards.iter().for_each(|x| {
         uid_map.insert(x.submitter.clone(), None);
@@ -53,7 +76,7 @@ async fn shards_to_quotes(
         Ok(users) => users.into_iter().for_each(|x| {
             let _ = uid_map.insert(x.uid, Some(x.cn));
         }),
-        Err(err) => return Err(HttpResponse::InternalServerError().body(err.to_string())),
+        Err(err) => return Err(ApiError::ldap(err)),
     }
 
     let mut quotes: Vec<QuoteResponse> = Vec::new();
@@ -90,16 +113,25 @@ async fn shards_to_quotes(
                     speaker,
                 }],
                 timestamp: shard.timestamp,
+                edited: shard.edited,
+                said_at: shard.said_at,
+                context: shard.context.clone(),
                 score: shard.score,
+                upvotes: shard.upvotes,
+                downvotes: shard.downvotes,
+                relevance: shard.relevance,
                 vote: shard.vote.clone(),
                 submitter,
                 hidden: hidden_actor.clone().and_then(|actor| {
                     Some(Hidden {
                         actor,
                         reason: shard.hidden_reason.clone()?,
+                        timestamp: shard.hidden_timestamp?,
                     })
                 }),
                 favorited: shard.favorited,
+                pending: shard.pending,
+                tags: shard.tags.clone(),
             });
         } else {
             quotes.last_mut().unwrap().shards.push(QuoteShardResponse {
@@ -138,47 +170,97 @@ fn format_reports(quotes: &[ReportedQuoteShard]) -> Vec<ReportedQuoteResponse> {
     reported_quotes.into_values().collect()
 }
 
-impl ResponseError for SqlxErrorOrResponse<'_> {
-    fn status_code(&self) -> StatusCode {
-        match self {
-            Self::SqlxError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::ResponseOwned(status_code, _) | Self::Response(status_code, _) => *status_code,
-        }
+async fn get_display_names(
+    uids: impl Iterator<Item = String>,
+    ldap: &ldap::client::LdapClient,
+) -> Result<HashMap<String, String>, ApiError> {
+    let uids: BTreeSet<String> = uids.collect();
+    match ldap::get_users(ldap, Vec::from_iter(uids).as_slice()).await {
+        Ok(users) => Ok(users.into_iter().map(|x| (x.uid, x.cn)).collect()),
+        Err(err) => Err(ApiError::ldap(err)),
     }
-    fn error_response(&self) -> HttpResponse {
-        match self {
-            Self::SqlxError(error) => {
-                HttpResponse::InternalServerError().body(format!("SQLX Error: {error}"))
-            }
-            Self::Response(status_code, body) => {
-                HttpResponse::with_body(*status_code, body.to_string().boxed())
-            }
-            Self::ResponseOwned(status_code, body) => {
-                HttpResponse::with_body(*status_code, body.clone().boxed())
-            }
-        }
+}
+
+/// `limit` as a SQL limit, where -1 means no limit
+fn resolve_limit(limit: Option<i64>, default: i64) -> Result<i64, ApiError> {
+    match limit {
+        None => Ok(default),
+        Some(-1) => Ok(i64::MAX),
+        Some(limit) if limit >= 0 => Ok(limit),
+        Some(_) => Err(ApiError::invalid_request(
+            "limit must be -1 for no limit, or at least 0.",
+        )),
     }
 }
 
-impl Display for SqlxErrorOrResponse<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        match self {
-            Self::SqlxError(error) => write!(f, "{error}"),
-            Self::Response(status_code, error_message) => {
-                write!(f, "{status_code}: {error_message}")
+fn to_user_response(uid: &str, names: &HashMap<String, String>) -> Option<UserResponse> {
+    names.get(uid).map(|cn| UserResponse {
+        uid: uid.to_string(),
+        cn: cn.clone(),
+    })
+}
+
+async fn revisions_to_history(
+    revisions: &[RevisionShard],
+    ldap: &ldap::client::LdapClient,
+) -> Result<Vec<QuoteRevisionResponse>, ApiError> {
+    let names = get_display_names(
+        revisions
+            .iter()
+            .flat_map(|x| [x.speaker.clone(), x.editor.clone()]),
+        ldap,
+    )
+    .await?;
+
+    let mut history: Vec<QuoteRevisionResponse> = Vec::new();
+    for shard in revisions {
+        let speaker = match to_user_response(&shard.speaker, &names) {
+            Some(speaker) => speaker,
+            None => continue,
+        };
+        match history.last_mut() {
+            Some(revision) if revision.revision == shard.revision => {
+                revision.shards.push(QuoteShardResponse {
+                    body: shard.body.clone(),
+                    speaker,
+                })
             }
-            Self::ResponseOwned(status_code, error_message) => {
-                write!(f, "{status_code}: {error_message}")
+            _ => {
+                let editor = match to_user_response(&shard.editor, &names) {
+                    Some(editor) => editor,
+                    None => continue,
+                };
+                history.push(QuoteRevisionResponse {
+                    revision: shard.revision,
+                    editor,
+                    timestamp: shard.timestamp,
+                    shards: vec![QuoteShardResponse {
+                        body: shard.body.clone(),
+                        speaker,
+                    }],
+                });
             }
         }
     }
+    Ok(history)
 }
 
-#[derive(Debug)]
-pub enum SqlxErrorOrResponse<'a> {
-    SqlxError(sqlx::Error),
-    Response(StatusCode, &'a str),
-    ResponseOwned(StatusCode, String),
+async fn format_hide_events(
+    events: &[HideEvent],
+    ldap: &ldap::client::LdapClient,
+) -> Result<Vec<HideEventResponse>, ApiError> {
+    let names = get_display_names(events.iter().map(|x| x.actor.clone()), ldap).await?;
+    Ok(events
+        .iter()
+        .filter_map(|event| {
+            Some(HideEventResponse {
+                action: event.action.clone(),
+                reason: event.reason.clone(),
+                actor: to_user_response(&event.actor, &names)?,
+                timestamp: event.timestamp,
+            })
+        })
+        .collect())
 }
 
 pub async fn hide_quote_by_id(
@@ -186,11 +268,12 @@ pub async fn hide_quote_by_id(
     user: User,
     reason: String,
     transaction: &mut Transaction<'_, Postgres>,
-) -> Result<(), SqlxErrorOrResponse<'static>> {
+) -> Result<(), ApiError> {
     let result = query!(
-        "INSERT INTO public.hidden(quote_id, reason, actor)
-            SELECT $1, $2, $3::varchar
+        "INSERT INTO hide_events(quote_id, action, reason, actor)
+            SELECT $1, 'hide', $2, $3::varchar
             WHERE $1 IN (SELECT id FROM quotes)
+                AND $1 NOT IN (SELECT quote_id FROM hidden)
                 AND ($4 OR $1 IN (
                     SELECT quote_id FROM shards s
                     WHERE s.speaker = $3
@@ -198,82 +281,247 @@ pub async fn hide_quote_by_id(
         id,
         reason,
         user.preferred_username,
-        user.admin() || !*SECURITY_ENABLED,
+        user.has_role(Role::Moderator) || !*SECURITY_ENABLED,
     )
     .execute(&mut **transaction)
     .await?;
     if result.rows_affected() == 0 {
-        Err(SqlxErrorOrResponse::Response(
-            StatusCode::BAD_REQUEST,
-            "Either you are not quoted in this quote or this quote does not exist.",
-        ))
-    } else {
-        log!(Level::Trace, "hid quote");
-        Ok(())
+        return Err(ApiError::bad_request(
+            ErrorCode::HideNotAllowed,
+            "Either you are not quoted in this quote, this quote is already hidden, or this quote does not exist.",
+        ));
+    }
+    log!(Level::Trace, "hid quote");
+
+    if user.has_role(Role::Moderator) || !*SECURITY_ENABLED {
+        log_moderation(
+            &mut **transaction,
+            &user.preferred_username,
+            ModerationAction::Hide,
+            Some(id),
+            Some(reason),
+        )
+        .await?;
     }
+    Ok(())
 }
 
-#[post("/quote", wrap = "CSHAuth::enabled()")]
-pub async fn create_quote(
-    state: Data<AppState>,
-    body: Json<NewQuote>,
+pub async fn unhide_quote_by_id(
+    id: i32,
     user: User,
-) -> impl Responder {
-    log!(Level::Info, "POST /api/quote");
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), ApiError> {
+    let result = query!(
+        "INSERT INTO hide_events(quote_id, action, actor)
+            SELECT $1, 'unhide', $2::varchar
+            WHERE $1 IN (
+                SELECT quote_id FROM hidden
+                WHERE $3 OR actor = $2
+            )",
+        id,
+        user.preferred_username,
+        user.has_role(Role::Moderator) || !*SECURITY_ENABLED,
+    )
+    .execute(&mut **transaction)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::bad_request(
+            ErrorCode::UnhideNotAllowed,
+            "Either you did not hide this quote or this quote is not hidden.",
+        ));
+    }
+    log!(Level::Trace, "unhid quote");
 
-    if body.shards.is_empty() {
-        return HttpResponse::BadRequest().body("No quote shards specified");
+    if user.has_role(Role::Moderator) || !*SECURITY_ENABLED {
+        log_moderation(
+            &mut **transaction,
+            &user.preferred_username,
+            ModerationAction::Unhide,
+            Some(id),
+            None,
+        )
+        .await?;
     }
-    if body.shards.len() > 6 {
-        return HttpResponse::BadRequest().body("Maximum of 6 shards exceeded.");
+    Ok(())
+}
+
+async fn validate_shards(
+    shards: &[NewQuoteShard],
+    submitter: &str,
+    ldap: &ldap::client::LdapClient,
+) -> Result<(), ApiError> {
+    if shards.is_empty() {
+        return Err(ApiError::bad_request(
+            ErrorCode::NoShards,
+            "No quote shards specified",
+        ));
     }
-    for shard in &body.shards {
+    if shards.len() > 6 {
+        return Err(ApiError::bad_request(
+            ErrorCode::TooManyShards,
+            "Maximum of 6 shards exceeded.",
+        )
+        .with_details(json!({ "max_shards": 6 })));
+    }
+    for shard in shards {
         if !is_valid_username(shard.speaker.as_str()) {
-            return HttpResponse::BadRequest().body("Invalid speaker username format specified.");
+            return Err(ApiError::bad_request(
+                ErrorCode::InvalidUsername,
+                "Invalid speaker username format specified.",
+            )
+            .with_details(json!({ "speaker": shard.speaker })));
         }
-        if user.preferred_username == shard.speaker {
-            return HttpResponse::BadRequest().body("Erm... maybe don't quote yourself?");
+        if submitter == shard.speaker {
+            return Err(ApiError::bad_request(
+                ErrorCode::SelfQuote,
+                "Erm... maybe don't quote yourself?",
+            ));
         }
     }
-    if !is_valid_username(user.preferred_username.as_str()) {
-        return HttpResponse::BadRequest()
-            .body("Invalid submitter username specified. SHOULD NEVER HAPPEN!");
+    if !is_valid_username(submitter) {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidUsername,
+            "Invalid submitter username specified. SHOULD NEVER HAPPEN!",
+        ));
     }
-    let mut users: Vec<String> = body.shards.iter().map(|x| x.speaker.clone()).collect();
-    users.push(user.preferred_username.clone());
-    match ldap::users_exist(&state.ldap, BTreeSet::from_iter(users.into_iter())).await {
-        Ok(exists) => {
-            if !exists {
-                return HttpResponse::BadRequest().body("Some users submitted do not exist.");
-            }
-        }
-        Err(err) => return HttpResponse::InternalServerError().body(err.to_string()),
+    let mut users: Vec<String> = shards.iter().map(|x| x.speaker.clone()).collect();
+    users.push(submitter.to_string());
+    match ldap::users_exist(ldap, BTreeSet::from_iter(users)).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ApiError::bad_request(
+            ErrorCode::UnknownUsers,
+            "Some users submitted do not exist.",
+        )),
+        Err(err) => Err(ApiError::ldap(err)),
     }
+}
 
-    let mut transaction = match open_transaction(&state.db).await {
-        Ok(t) => t,
-        Err(res) => return res,
-    };
+/// Lowercases, dedupes and validates tags
+fn normalize_tags(tags: &[String]) -> Result<Vec<String>, ApiError> {
+    let tags: BTreeSet<String> = tags.iter().map(|x| x.trim().to_lowercase()).collect();
+    if tags.len() > MAX_TAGS {
+        return Err(
+            ApiError::bad_request(ErrorCode::InvalidTag, "Maximum of 10 tags exceeded.")
+                .with_details(json!({ "max_tags": MAX_TAGS })),
+        );
+    }
+    if let Some(tag) = tags.iter().find(|x| !is_valid_tag(x)) {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidTag,
+            "Tags must be up to 32 lowercase letters, digits and dashes.",
+        )
+        .with_details(json!({ "tag": tag })));
+    }
+    Ok(tags.into_iter().collect())
+}
+
+/// Trims `context` to `None` if empty and checks `said_at` is not in the future
+fn validate_context(quote: &NewQuote) -> Result<Option<String>, ApiError> {
+    if quote
+        .said_at
+        .is_some_and(|said_at| said_at > chrono::Utc::now().naive_utc())
+    {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidContext,
+            "Quotes can't be said in the future.",
+        ));
+    }
+    let context = quote
+        .context
+        .as_deref()
+        .map(str::trim)
+        .filter(|x| !x.is_empty());
+    if context.is_some_and(|x| x.chars().count() > MAX_CONTEXT_LENGTH) {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidContext,
+            "Context must be at most 500 characters.",
+        )
+        .with_details(json!({ "max_length": MAX_CONTEXT_LENGTH })));
+    }
+    Ok(context.map(String::from))
+}
 
-    let id: i32;
-    match log_query_as(
+async fn replace_tags(
+    id: i32,
+    tags: &[String],
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<(), ApiError> {
+    query!("DELETE FROM quote_tags WHERE quote_id = $1", id)
+        .execute(&mut **transaction)
+        .await?;
+    query!(
+        "INSERT INTO quote_tags (quote_id, tag)
+        SELECT $1, tag FROM UNNEST($2::varchar[]) AS t(tag)",
+        id,
+        tags,
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
+
+async fn check_opt_outs(shards: &[NewQuoteShard], db: &Pool<Postgres>) -> Result<(), ApiError> {
+    let speakers: Vec<String> = shards.iter().map(|x| x.speaker.clone()).collect();
+    let opted_out: Vec<String> = query!(
+        "SELECT username FROM user_settings WHERE opt_out AND username = ANY($1)",
+        speakers.as_slice(),
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|x| x.username)
+    .collect();
+    if opted_out.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::bad_request(
+            ErrorCode::OptedOut,
+            "Some speakers have opted out of being quoted.",
+        )
+        .with_details(json!({ "speakers": opted_out })))
+    }
+}
+
+/// Create a quote
+#[utoipa::path(
+    tag = "quotes",
+    request_body = NewQuote,
+    responses(
+        (status = 200, description = "Quote created"),
+        (status = 400, description = "Invalid shards, or speakers that don't exist or opted out", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[post("/quote", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn create_quote(
+    state: Data<AppState>,
+    body: Json<NewQuote>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    log!(Level::Info, "POST /api/quote");
+
+    validate_shards(&body.shards, &user.preferred_username, &state.ldap).await?;
+    check_opt_outs(&body.shards, &state.db).await?;
+    let tags = normalize_tags(body.tags.as_deref().unwrap_or_default())?;
+    let context = validate_context(&body)?;
+
+    let mut transaction = open_transaction(&state.db).await?;
+
+    let (tx, ids) = log_query_as(
         query_as!(
             ID,
-            "INSERT INTO quotes(submitter) VALUES ($1) RETURNING id",
-            user.preferred_username
+            "INSERT INTO quotes(submitter, said_at, context) VALUES ($1, $2, $3) RETURNING id",
+            user.preferred_username,
+            body.said_at,
+            context,
         )
         .fetch_all(&mut *transaction)
         .await,
         Some(transaction),
     )
-    .await
-    {
-        Ok((tx, i)) => {
-            transaction = tx.unwrap();
-            id = i[0].id;
-        }
-        Err(res) => return res,
-    }
+    .await?;
+    transaction = tx.unwrap();
+    let id = ids[0].id;
     log!(Level::Trace, "created a new entry in quote table");
 
     let ids: Vec<i32> = vec![id; body.shards.len()];
@@ -281,7 +529,7 @@ pub async fn create_quote(
     let bodies: Vec<String> = body.shards.iter().map(|s| s.body.clone()).collect();
     let speakers: Vec<String> = body.shards.iter().map(|s| s.speaker.clone()).collect();
 
-    match log_query(
+    let (tx, _) = log_query(
         query!(
             "INSERT INTO Shards (quote_id, index, body, speaker)
             SELECT quote_id, index, body, speaker
@@ -292,89 +540,139 @@ pub async fn create_quote(
             speakers.as_slice()
         )
         .execute(&mut *transaction)
-        .await, Some(transaction)).await {
-        Ok((tx, _)) => transaction = tx.unwrap(),
-        Err(res) => return res,
-    }
+        .await, Some(transaction)).await?;
+    transaction = tx.unwrap();
 
     log!(Level::Trace, "created quote shards");
 
-    match transaction.commit().await {
-        Ok(_) => {
-            for shard in &body.shards {
-                if let Err(err) = send_ping(
-                    shard.speaker.clone(),
-                    format!(
-                        "You were quoted by {}. Check it out at Quotefault!",
-                        user.preferred_username
-                    ),
-                ) {
-                    log!(Level::Error, "Failed to ping: {}", err);
-                }
-            }
-            HttpResponse::Ok().body("")
-        }
-        Err(e) => {
-            log!(Level::Error, "Transaction failed to commit");
-            HttpResponse::InternalServerError().body(e.to_string())
+    replace_tags(id, &tags, &mut transaction).await?;
+
+    let (tx, pending) = log_query_as(
+        query!(
+            "INSERT INTO pending_approvals (quote_id, speaker)
+            SELECT DISTINCT $1::int4, speaker FROM shards
+            WHERE quote_id = $1
+            AND speaker IN (SELECT username FROM user_settings WHERE require_approval)
+            RETURNING speaker",
+            id
+        )
+        .fetch_all(&mut *transaction)
+        .await,
+        Some(transaction),
+    )
+    .await?;
+    let pending: Vec<String> = pending.into_iter().map(|x| x.speaker).collect();
+
+    tx.unwrap().commit().await?;
+    for shard in &body.shards {
+        let message = if pending.contains(&shard.speaker) {
+            format!(
+                "You were quoted by {}. Approve or reject it at Quotefault!",
+                user.preferred_username
+            )
+        } else {
+            format!(
+                "You were quoted by {}. Check it out at Quotefault!",
+                user.preferred_username
+            )
+        };
+        if let Err(err) = send_ping(shard.speaker.clone(), message) {
+            log!(Level::Error, "Failed to ping: {}", err);
         }
     }
+    Ok(HttpResponse::Ok().body(""))
 }
 
-#[delete("/quote/{id}", wrap = "CSHAuth::enabled()")]
-pub async fn delete_quote(state: Data<AppState>, path: Path<(i32,)>, user: User) -> impl Responder {
+/// Delete a quote, as its submitter or an admin
+#[utoipa::path(
+    tag = "quotes",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote deleted"),
+        (status = 400, description = "Not your quote or quote does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[delete("/quote/{id}", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn delete_quote(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
 
-    let mut transaction = match open_transaction(&state.db).await {
-        Ok(t) => t,
-        Err(res) => return res,
-    };
+    let mut transaction = open_transaction(&state.db).await?;
 
-    match log_query(
+    let (tx, result) = log_query_as(
         query!(
-            "DELETE FROM quotes WHERE id = $1 AND submitter = $2",
+            "DELETE FROM quotes WHERE id = $1 AND (submitter = $2 OR $3) RETURNING submitter",
             id,
-            user.preferred_username
+            user.preferred_username,
+            user.has_role(Role::Moderator) || !*SECURITY_ENABLED,
         )
-        .execute(&mut *transaction)
+        .fetch_all(&mut *transaction)
         .await,
         Some(transaction),
     )
-    .await
-    {
-        Ok((tx, result)) => {
-            if result.rows_affected() == 0 {
-                return HttpResponse::BadRequest()
-                    .body("Either this is not your quote or this quote does not exist.");
-            }
-            transaction = tx.unwrap()
-        }
-        Err(res) => return res,
+    .await?;
+    if result.is_empty() {
+        return Err(ApiError::bad_request(
+            ErrorCode::NotSubmitter,
+            "Either this is not your quote or this quote does not exist.",
+        ));
     }
+    transaction = tx.unwrap();
+    let submitter = result[0].submitter.clone();
 
     log!(Level::Trace, "deleted quote and all shards");
 
-    match transaction.commit().await {
-        Ok(_) => HttpResponse::Ok().body(""),
-        Err(e) => {
-            log!(Level::Error, "Transaction failed to commit");
-            HttpResponse::InternalServerError().body(e.to_string())
-        }
+    if submitter != user.preferred_username {
+        let (tx, _) = log_query(
+            log_moderation(
+                &mut *transaction,
+                &user.preferred_username,
+                ModerationAction::Delete,
+                Some(id),
+                Some(format!("submitted by {submitter}")),
+            )
+            .await,
+            Some(transaction),
+        )
+        .await?;
+        transaction = tx.unwrap();
     }
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().body(""))
 }
 
-#[put("/quote/{id}/hide", wrap = "CSHAuth::enabled()")]
+/// Hide a quote, as one of its speakers or an admin
+#[utoipa::path(
+    tag = "moderation",
+    request_body = Reason,
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote hidden"),
+        (status = 400, description = "Not quoted, already hidden or quote does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[put("/quote/{id}/hide", wrap = "CSHAuth::require_role(Role::Member)")]
 pub async fn hide_quote(
     state: Data<AppState>,
     path: Path<(i32,)>,
     user: User,
     Json(reason): Json<Reason>,
-) -> Result<HttpResponse, SqlxErrorOrResponse<'static>> {
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
 
     if reason.reason.len() < 10 {
-        return Err(SqlxErrorOrResponse::Response(
-            StatusCode::BAD_REQUEST,
+        return Err(ApiError::bad_request(
+            ErrorCode::ReasonTooShort,
             "Reason must be at least 10 characters",
         ));
     }
@@ -390,31 +688,108 @@ pub async fn hide_quote(
     Ok(HttpResponse::Ok().body(""))
 }
 
-#[post("/quote/{id}/report", wrap = "CSHAuth::enabled()")]
+/// Unhide a quote, as the user who hid it or an admin
+#[utoipa::path(
+    tag = "moderation",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote unhidden"),
+        (status = 400, description = "Not hidden by you or quote is not hidden", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[delete("/quote/{id}/hide", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn unhide_quote(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+
+    state
+        .db
+        .acquire()
+        .await?
+        .transaction(|transaction| {
+            Box::pin(async move { unhide_quote_by_id(id, user, transaction).await })
+        })
+        .await?;
+    Ok(HttpResponse::Ok().body(""))
+}
+
+/// List every time a quote was hidden or unhidden
+#[utoipa::path(
+    tag = "moderation",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, body = [HideEventResponse]),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/quote/{id}/hide/history", wrap = "CSHAuth::enabled()")]
+pub async fn get_hide_history(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+
+    let events = query_as!(
+        HideEvent,
+        "SELECT e.action AS \"action: HideAction\", e.reason, e.actor, e.timestamp
+        FROM hide_events e
+        WHERE e.quote_id = $1 AND quote_visible($1, $2, $3)
+        ORDER BY e.id DESC",
+        id,
+        user.preferred_username,
+        user.has_role(Role::Moderator) || !*SECURITY_ENABLED,
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(format_hide_events(&events, &state.ldap).await?))
+}
+
+/// Report a quote
+#[utoipa::path(
+    tag = "moderation",
+    request_body = Reason,
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote reported"),
+        (status = 400, description = "Already reported or quote does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[post("/quote/{id}/report", wrap = "CSHAuth::require_role(Role::Member)")]
 pub async fn report_quote(
     state: Data<AppState>,
     path: Path<(i32,)>,
     body: Json<Reason>,
     user: User,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
 
-    let mut transaction = match open_transaction(&state.db).await {
-        Ok(t) => t,
-        Err(res) => return res,
-    };
+    let mut transaction = open_transaction(&state.db).await?;
 
     let mut hasher = Sha3_256::new();
     hasher.update(format!("{}coleandethanwerehere", user.preferred_username).as_str()); // >:)
     let result = hasher.finalize();
 
-    match log_query(
+    let (tx, result) = log_query(
         query!(
             "INSERT INTO reports (quote_id, reason, submitter_hash)
             SELECT $1, $2, $3
             WHERE $1 IN (
                 SELECT id FROM quotes
                 WHERE id NOT IN (SELECT quote_id FROM hidden)
+                AND id NOT IN (SELECT quote_id FROM pending_approvals)
             )
             ON CONFLICT DO NOTHING",
             id,
@@ -425,56 +800,159 @@ pub async fn report_quote(
         .await,
         Some(transaction),
     )
-    .await
-    {
-        Ok((tx, result)) => {
-            transaction = tx.unwrap();
-            if result.rows_affected() == 0 {
-                return HttpResponse::BadRequest()
-                    .body("You have already reported this quote or quote does not exist");
-            }
-        }
-        Err(res) => return res,
-    };
+    .await?;
+    transaction = tx.unwrap();
+    if result.rows_affected() == 0 {
+        return Err(ApiError::bad_request(
+            ErrorCode::AlreadyReported,
+            "You have already reported this quote or quote does not exist",
+        ));
+    }
     log!(Level::Trace, "created a new report");
 
-    match transaction.commit().await {
-        Ok(_) => HttpResponse::Ok().body(""),
-        Err(e) => {
-            log!(Level::Error, "Transaction failed to commit");
-            HttpResponse::InternalServerError().body(e.to_string())
-        }
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().body(""))
+}
+
+/// Approve a quote you are quoted in, publishing it once every speaker has
+#[utoipa::path(
+    tag = "quotes",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote approved"),
+        (status = 400, description = "Quote is not awaiting your approval", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[post("/quote/{id}/approve", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn approve_quote(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+
+    let approved = query!(
+        "DELETE FROM pending_approvals p
+        USING quotes q
+        WHERE q.id = p.quote_id AND p.quote_id = $1 AND p.speaker = $2
+        RETURNING q.submitter",
+        id,
+        user.preferred_username,
+    )
+    .fetch_optional(&state.db)
+    .await?;
+    let Some(approved) = approved else {
+        return Err(ApiError::bad_request(
+            ErrorCode::NoPendingApproval,
+            "This quote is not awaiting your approval.",
+        ));
+    };
+
+    if let Err(err) = send_ping(
+        approved.submitter,
+        format!(
+            "{} approved your quote. Check it out at Quotefault!",
+            user.preferred_username
+        ),
+    ) {
+        log!(Level::Error, "Failed to ping: {}", err);
     }
+    Ok(HttpResponse::Ok().body(""))
 }
 
-#[get("/quote/{id}", wrap = "CSHAuth::enabled()")]
-pub async fn get_quote(state: Data<AppState>, path: Path<(i32,)>, user: User) -> impl Responder {
+/// Reject a quote you are quoted in, hiding it
+#[utoipa::path(
+    tag = "quotes",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote rejected and hidden"),
+        (status = 400, description = "Quote is not awaiting your approval", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[post("/quote/{id}/reject", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn reject_quote(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
 
-    match log_query_as(
+    let mut transaction = state.db.begin().await?;
+    let rejected = query!(
+        "DELETE FROM pending_approvals p
+        USING quotes q
+        WHERE q.id = p.quote_id AND p.quote_id = $1 AND p.speaker = $2
+        RETURNING q.submitter",
+        id,
+        user.preferred_username,
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    let Some(rejected) = rejected else {
+        return Err(ApiError::bad_request(
+            ErrorCode::NoPendingApproval,
+            "This quote is not awaiting your approval.",
+        ));
+    };
+    // Other speakers may have approved the quote already, so it is hidden
+    // rather than deleted, and the rejecting speaker can unhide it later
+    query!(
+        "INSERT INTO hide_events (quote_id, action, reason, actor)
+        SELECT $1, 'hide', $2, $3::varchar
+        WHERE $1 NOT IN (SELECT quote_id FROM hidden)",
+        id,
+        REJECT_REASON,
+        user.preferred_username,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    if let Err(err) = send_ping(
+        rejected.submitter,
+        format!(
+            "{} rejected a quote you submitted, so it was hidden.",
+            user.preferred_username
+        ),
+    ) {
+        log!(Level::Error, "Failed to ping: {}", err);
+    }
+    Ok(HttpResponse::Ok().body(""))
+}
+
+/// Shards of the quotes among `ids` that `user` can see, in the order of `ids`
+async fn fetch_quotes_by_id(
+    state: &AppState,
+    ids: &[i32],
+    user: &User,
+) -> Result<Vec<QuoteShard>, ApiError> {
+    let (_, shards) = log_query_as(
         query_as!(
             QuoteShard,
             "SELECT pq.id as \"id!\", s.index as \"index!\", pq.submitter as \"submitter!\",
-            pq.timestamp as \"timestamp!\", s.body as \"body!\", s.speaker as \"speaker!\",
-            hidden.reason as \"hidden_reason: Option<String>\", hidden.actor as \"hidden_actor: Option<String>\", 
+            pq.timestamp as \"timestamp!\", pq.edited as \"edited\",
+            pq.said_at as \"said_at\", pq.context as \"context\",
+            s.body as \"body!\", s.speaker as \"speaker!\",
+            hidden.reason as \"hidden_reason\", hidden.actor as \"hidden_actor\",
+            hidden.timestamp as \"hidden_timestamp\",
             v.vote as \"vote: Option<Vote>\",
-            (CASE WHEN t.score IS NULL THEN 0 ELSE t.score END) AS \"score!\",
-            (CASE WHEN f.username IS NULL THEN FALSE ELSE TRUE END) AS \"favorited!\"
+            pq.score::int8 AS \"score!\",
+            pq.upvotes::int8 AS \"upvotes!\", pq.downvotes::int8 AS \"downvotes!\",
+            NULL::real AS \"relevance\", NULL::text AS \"sort_key\",
+            (CASE WHEN f.username IS NULL THEN FALSE ELSE TRUE END) AS \"favorited!\",
+            pq.id IN (SELECT quote_id FROM pending_approvals) AS \"pending!\",
+            ARRAY(
+                SELECT tag FROM quote_tags WHERE quote_id = pq.id ORDER BY tag
+            ) AS \"tags!\"
             FROM (
                 SELECT * FROM quotes q
-                WHERE q.id = $1
-                AND CASE
-                    WHEN $3 THEN TRUE
-                    ELSE (CASE
-                        WHEN q.id IN (SELECT quote_id FROM hidden) AND
-                        (q.submitter=$2 OR $2 IN (
-                            SELECT speaker FROM shards
-                            WHERE quote_id=q.id))
-                        THEN TRUE
-                        ELSE q.id NOT IN (SELECT quote_id FROM hidden)
-                    END)
-                END
-                ORDER BY q.id DESC
+                WHERE q.id = ANY($1) AND quote_visible(q.id, $2, $3)
             ) AS pq
             LEFT JOIN hidden ON hidden.quote_id = pq.id
             LEFT JOIN shards s ON s.quote_id = pq.id
@@ -482,221 +960,986 @@ pub async fn get_quote(state: Data<AppState>, path: Path<(i32,)>, user: User) ->
                 SELECT quote_id, vote FROM votes
                 WHERE submitter=$2
             ) v ON v.quote_id = pq.id
-            LEFT JOIN (
-                SELECT
-                    quote_id,
-                    SUM(
-                        CASE
-                            WHEN vote='upvote' THEN 1 
-                            WHEN vote='downvote' THEN -1
-                            ELSE 0
-                        END
-                    ) AS score
-                FROM votes
-                GROUP BY quote_id
-            ) t ON t.quote_id = pq.id
             LEFT JOIN (
                 SELECT quote_id, username FROM favorites
                 WHERE username=$2
             ) f ON f.quote_id = pq.id
-            ORDER BY timestamp DESC, pq.id DESC, s.index",
-            id,
+            ORDER BY array_position($1, pq.id), s.index",
+            ids,
             user.preferred_username,
-            user.admin() || !*SECURITY_ENABLED,
+            user.has_role(Role::Moderator) || !*SECURITY_ENABLED,
         )
         .fetch_all(&state.db)
         .await,
         None,
     )
-    .await
-    {
-        Ok((_, shards)) => {
-            if shards.is_empty() {
-                HttpResponse::NotFound().body("Quote could not be found")
-            } else {
-                match shards_to_quotes(shards.as_slice(), &state.ldap).await {
-                    Ok(quotes) => HttpResponse::Ok().json(quotes.get(0).unwrap()),
-                    Err(res) => res,
+    .await?;
+    Ok(shards)
+}
+
+/// Get a quote
+#[utoipa::path(
+    tag = "quotes",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, body = QuoteResponse),
+        (status = 404, description = "Quote could not be found", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/quote/{id}", wrap = "CSHAuth::enabled()")]
+pub async fn get_quote(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+
+    let shards = fetch_quotes_by_id(&state, &[id], &user).await?;
+    if shards.is_empty() {
+        return Err(ApiError::not_found(
+            ErrorCode::QuoteNotFound,
+            "Quote could not be found",
+        ));
+    }
+    if shards[0].hidden_actor.is_some() && (user.has_role(Role::Moderator) || !*SECURITY_ENABLED) {
+        log_query(
+            log_moderation(
+                &state.db,
+                &user.preferred_username,
+                ModerationAction::ViewHidden,
+                Some(id),
+                None,
+            )
+            .await,
+            None,
+        )
+        .await?;
+    }
+    let quotes = shards_to_quotes(shards.as_slice(), &state.ldap).await?;
+    Ok(HttpResponse::Ok().json(quotes.first().unwrap()))
+}
+
+/// Replace the shards of a quote, as its submitter or an admin
+#[utoipa::path(
+    tag = "quotes",
+    request_body = NewQuote,
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote edited"),
+        (status = 400, description = "Invalid shards, not your quote or quote does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[put("/quote/{id}", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn edit_quote(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    body: Json<NewQuote>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+    let ldap = state.ldap.clone();
+
+    check_opt_outs(&body.shards, &state.db).await?;
+    let tags = body.tags.as_deref().map(normalize_tags).transpose()?;
+    let context = validate_context(&body)?;
+
+    state
+        .db
+        .acquire()
+        .await?
+        .transaction(|transaction| {
+            Box::pin(async move {
+                let submitter = match query!(
+                    "SELECT submitter FROM quotes WHERE id = $1 FOR UPDATE",
+                    id
+                )
+                .fetch_optional(&mut **transaction)
+                .await?
+                {
+                    Some(quote)
+                        if quote.submitter == user.preferred_username
+                            || user.has_role(Role::Moderator)
+                            || !*SECURITY_ENABLED =>
+                    {
+                        quote.submitter
+                    }
+                    _ => {
+                        return Err(ApiError::bad_request(ErrorCode::NotSubmitter, "Either this is not your quote or this quote does not exist."))
+                    }
+                };
+
+                validate_shards(&body.shards, &submitter, &ldap).await?;
+
+                query!(
+                    "INSERT INTO revisions (quote_id, revision, index, body, speaker, editor)
+                    SELECT quote_id, (
+                        SELECT COALESCE(MAX(revision), 0) + 1 FROM revisions WHERE quote_id = $1
+                    ), index, body, speaker, $2
+                    FROM shards WHERE quote_id = $1",
+                    id,
+                    user.preferred_username,
+                )
+                .execute(&mut **transaction)
+                .await?;
+                log!(Level::Trace, "saved previous quote revision");
+
+                query!("DELETE FROM shards WHERE quote_id = $1", id)
+                    .execute(&mut **transaction)
+                    .await?;
+
+                let ids: Vec<i32> = vec![id; body.shards.len()];
+                let indices: Vec<i16> = (1..=body.shards.len()).map(|a| a as i16).collect();
+                let bodies: Vec<String> = body.shards.iter().map(|s| s.body.clone()).collect();
+                let speakers: Vec<String> =
+                    body.shards.iter().map(|s| s.speaker.clone()).collect();
+
+                query!(
+                    "INSERT INTO Shards (quote_id, index, body, speaker)
+                    SELECT quote_id, index, body, speaker
+                    FROM UNNEST($1::int4[], $2::int2[], $3::text[], $4::varchar[]) as a(quote_id, index, body, speaker)",
+                    ids.as_slice(),
+                    indices.as_slice(),
+                    bodies.as_slice(),
+                    speakers.as_slice()
+                )
+                .execute(&mut **transaction)
+                .await?;
+
+                // Speakers added by this edit who require approval have to
+                // approve it, and removed speakers no longer do
+                query!(
+                    "DELETE FROM pending_approvals
+                    WHERE quote_id = $1
+                    AND speaker NOT IN (SELECT speaker FROM shards WHERE quote_id = $1)",
+                    id
+                )
+                .execute(&mut **transaction)
+                .await?;
+                query!(
+                    "INSERT INTO pending_approvals (quote_id, speaker)
+                    SELECT DISTINCT $1::int4, speaker FROM shards
+                    WHERE quote_id = $1
+                    AND speaker IN (SELECT username FROM user_settings WHERE require_approval)
+                    AND speaker NOT IN (
+                        SELECT speaker FROM revisions
+                        WHERE quote_id = $1
+                        AND revision = (SELECT MAX(revision) FROM revisions WHERE quote_id = $1)
+                    )
+                    ON CONFLICT DO NOTHING",
+                    id
+                )
+                .execute(&mut **transaction)
+                .await?;
+
+                query!(
+                    "UPDATE quotes
+                    SET edited = CURRENT_TIMESTAMP,
+                        said_at = $2,
+                        context = $3
+                    WHERE id = $1",
+                    id,
+                    body.said_at,
+                    context,
+                )
+                .execute(&mut **transaction)
+                .await?;
+                log!(Level::Trace, "replaced quote shards");
+
+                if let Some(tags) = &tags {
+                    replace_tags(id, tags, transaction).await?;
                 }
-            }
-        }
-        Err(res) => res,
+
+                if submitter != user.preferred_username {
+                    log_moderation(
+                        &mut **transaction,
+                        &user.preferred_username,
+                        ModerationAction::Edit,
+                        Some(id),
+                        None,
+                    )
+                    .await?;
+                }
+
+                Ok(())
+            })
+        })
+        .await?;
+    Ok(HttpResponse::Ok().body(""))
+}
+
+/// List the previous revisions of a quote
+#[utoipa::path(
+    tag = "quotes",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, body = [QuoteRevisionResponse]),
+        (status = 404, description = "Quote could not be found", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/quote/{id}/history", wrap = "CSHAuth::enabled()")]
+pub async fn get_quote_history(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+
+    let visible = query!(
+        "SELECT id FROM quotes WHERE id = $1 AND quote_visible(id, $2, $3)",
+        id,
+        user.preferred_username,
+        user.has_role(Role::Moderator) || !*SECURITY_ENABLED,
+    )
+    .fetch_optional(&state.db)
+    .await?;
+    if visible.is_none() {
+        return Err(ApiError::not_found(
+            ErrorCode::QuoteNotFound,
+            "Quote could not be found",
+        ));
     }
+
+    let revisions = query_as!(
+        RevisionShard,
+        "SELECT revision, index, body, speaker, editor, timestamp
+        FROM revisions
+        WHERE quote_id = $1
+        ORDER BY revision DESC, index",
+        id,
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(revisions_to_history(&revisions, &state.ldap).await?))
 }
 
-#[post("/quote/{id}/vote", wrap = "CSHAuth::enabled()")]
+/// Vote on a quote
+#[utoipa::path(
+    tag = "votes",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+        VoteParams,
+    ),
+    responses(
+        (status = 200, description = "Vote recorded"),
+        (status = 400, description = "Quote does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[post("/quote/{id}/vote", wrap = "CSHAuth::require_role(Role::Member)")]
 pub async fn vote_quote(
     state: Data<AppState>,
     path: Path<(i32,)>,
     params: web::Query<VoteParams>,
     user: User,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
     let vote = params.vote.clone();
 
-    let mut transaction = match open_transaction(&state.db).await {
-        Ok(t) => t,
-        Err(res) => return res,
-    };
+    let mut transaction = open_transaction(&state.db).await?;
 
-    match log_query(
+    let (tx, result) = log_query(
         query!(
             "INSERT INTO votes (quote_id, vote, submitter)
             SELECT $1, $2, $3
             WHERE $1 IN (
                 SELECT id FROM quotes
                 WHERE CASE WHEN $4 THEN true ELSE id NOT IN (SELECT quote_id FROM hidden) END
+                AND id NOT IN (SELECT quote_id FROM pending_approvals)
             )
             ON CONFLICT (quote_id, submitter)
             DO UPDATE SET vote=$2",
             id,
             vote as Vote,
             user.preferred_username,
-            user.admin() || !*SECURITY_ENABLED
+            user.has_role(Role::Moderator) || !*SECURITY_ENABLED
         )
         .execute(&mut *transaction)
         .await,
         Some(transaction),
     )
-    .await
-    {
-        Ok((tx, result)) => {
-            transaction = tx.unwrap();
-            if result.rows_affected() == 0 {
-                return HttpResponse::BadRequest().body("Quote does not exist");
-            }
-        }
-        Err(res) => return res,
+    .await?;
+    transaction = tx.unwrap();
+    if result.rows_affected() == 0 {
+        return Err(ApiError::bad_request(
+            ErrorCode::QuoteNotFound,
+            "Quote does not exist",
+        ));
     }
 
-    match transaction.commit().await {
-        Ok(_) => HttpResponse::Ok().body(""),
-        Err(e) => {
-            log!(Level::Error, "Transaction failed to commit");
-            HttpResponse::InternalServerError().body(e.to_string())
-        }
-    }
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().body(""))
 }
 
-#[delete("/quote/{id}/vote", wrap = "CSHAuth::enabled()")]
-pub async fn unvote_quote(state: Data<AppState>, path: Path<(i32,)>, user: User) -> impl Responder {
+/// Remove your vote on a quote
+#[utoipa::path(
+    tag = "votes",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Vote removed"),
+        (status = 400, description = "Quote does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[delete("/quote/{id}/vote", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn unvote_quote(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
 
-    let mut transaction = match open_transaction(&state.db).await {
-        Ok(t) => t,
-        Err(res) => return res,
-    };
+    let mut transaction = open_transaction(&state.db).await?;
 
-    match log_query(
+    let (tx, result) = log_query(
         query!(
             "DELETE FROM votes 
             WHERE quote_id=$1 AND submitter=$2
             AND $1 IN (
                 SELECT id FROM quotes
                 WHERE CASE WHEN $3 THEN true ELSE id NOT IN (SELECT quote_id FROM hidden) END
+                AND id NOT IN (SELECT quote_id FROM pending_approvals)
             )",
             id,
             user.preferred_username,
-            user.admin() || !*SECURITY_ENABLED
+            user.has_role(Role::Moderator) || !*SECURITY_ENABLED
         )
         .execute(&mut *transaction)
         .await,
         Some(transaction),
     )
-    .await
-    {
-        Ok((tx, result)) => {
-            transaction = tx.unwrap();
-            if result.rows_affected() == 0 {
-                return HttpResponse::BadRequest().body("Quote does not exist");
-            }
-        }
-        Err(res) => return res,
+    .await?;
+    transaction = tx.unwrap();
+    if result.rows_affected() == 0 {
+        return Err(ApiError::bad_request(
+            ErrorCode::QuoteNotFound,
+            "Quote does not exist",
+        ));
     }
 
-    match transaction.commit().await {
-        Ok(_) => HttpResponse::Ok().body(""),
-        Err(e) => {
-            log!(Level::Error, "Transaction failed to commit");
-            HttpResponse::InternalServerError().body(e.to_string())
-        }
-    }
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().body(""))
 }
 
-#[get("/quotes", wrap = "CSHAuth::enabled()")]
-pub async fn get_quotes(
+/// List who voted on a quote
+#[utoipa::path(
+    tag = "votes",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, body = [QuoteVote]),
+        (status = 404, description = "Quote could not be found", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/quote/{id}/votes", wrap = "CSHAuth::admin_only()")]
+pub async fn get_quote_votes(
     state: Data<AppState>,
-    params: web::Query<FetchParams>,
-    user: User,
-) -> impl Responder {
-    let limit: i64 = params
-        .limit
-        .map(|x| if x == -1 { i64::MAX } else { x })
-        .unwrap_or(10);
+    path: Path<(i32,)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+
+    let exists = query!(
+        "SELECT EXISTS (SELECT 1 FROM quotes WHERE id = $1) AS \"exists!\"",
+        id
+    )
+    .fetch_one(&state.db)
+    .await?
+    .exists;
+    if !exists {
+        return Err(ApiError::not_found(
+            ErrorCode::QuoteNotFound,
+            "Quote could not be found",
+        ));
+    }
+
+    let votes = query_as!(
+        QuoteVote,
+        "SELECT submitter AS voter, vote AS \"vote: Vote\", timestamp
+        FROM votes
+        WHERE quote_id = $1
+        ORDER BY timestamp DESC, submitter",
+        id
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(votes))
+}
+
+/// Quotes matching `params` that `user` can see, as shards
+async fn fetch_quotes(
+    state: &AppState,
+    params: &FetchParams,
+    user: &User,
+    limit: i64,
+    sort: &str,
+    sort_direction: bool,
+    cursor: Option<&QuoteCursor>,
+) -> Result<Vec<QuoteShard>, ApiError> {
     let lt_qid: i32 = params.lt.unwrap_or(0);
-    let query = params
-        .q
-        .clone()
-        .map_or("%".to_string(), |q| format!("%{q}%"));
-    let speaker = params.speaker.clone().unwrap_or("%".to_string());
-    let submitter = params.submitter.clone().unwrap_or("%".to_string());
-    let involved = params.involved.clone().unwrap_or("%".to_string());
+    let query = params.q.clone().filter(|q| !q.trim().is_empty());
+    let match_all = params.match_mode == Some(MatchMode::All);
     let hidden = params.hidden.unwrap_or(false);
     let filter_by_hidden = params.hidden.is_some();
     let favorited = params.favorited.unwrap_or(false);
-    let sort = params.sort.as_ref().is_some_and(|s| s == "votes");
-    let sort_direction = params.sort_direction.is_some_and(|d| d);
-    match log_query_as(
+    let pending = params.pending.unwrap_or(false);
+    let tag = params.tag.as_ref().map(|x| x.trim().to_lowercase());
+    if hidden && (user.has_role(Role::Moderator) || !*SECURITY_ENABLED) {
+        log_query(
+            log_moderation(
+                &state.db,
+                &user.preferred_username,
+                ModerationAction::ViewHidden,
+                None,
+                None,
+            )
+            .await,
+            None,
+        )
+        .await?;
+    }
+    let (_, shards) = log_query_as(
         query_file_as!(
             QuoteShard,
             "queries/get_quotes.sql",
-            limit,                              // $1
-            lt_qid,                             // $2
-            query,                              // $3
-            speaker,                            // $4
-            submitter,                          // $5
-            hidden,                             // $6
-            filter_by_hidden,                   // $7
-            user.preferred_username,            // $8
-            user.admin() || !*SECURITY_ENABLED, // $9
-            involved,                           // $10
-            favorited,                          // $11
-            sort,                               // $12
-            sort_direction,                     // $13
+            limit,                                                // $1
+            lt_qid,                                               // $2
+            query,                                                // $3
+            &params.speaker,                                      // $4
+            &params.submitter,                                    // $5
+            hidden,                                               // $6
+            filter_by_hidden,                                     // $7
+            user.preferred_username,                              // $8
+            user.has_role(Role::Moderator) || !*SECURITY_ENABLED, // $9
+            &params.involved,                                     // $10
+            favorited,                                            // $11
+            sort,                                                 // $12
+            sort_direction,                                       // $13
+            cursor.map(|c| &c.key),                               // $14
+            cursor.map(|c| c.id),                                 // $15
+            pending,                                              // $16
+            tag,                                                  // $17
+            params.said_after,                                    // $18
+            params.said_before,                                   // $19
+            params.after,                                         // $20
+            params.before,                                        // $21
+            match_all,                                            // $22
         )
         .fetch_all(&state.db)
         .await,
         None,
     )
-    .await
+    .await?;
+    Ok(shards)
+}
+
+/// List quotes
+#[utoipa::path(
+    tag = "quotes",
+    params(FetchParams),
+    responses(
+        (status = 200, body = QuotesResponse),
+        (status = 400, description = "Invalid cursor", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/quotes", wrap = "CSHAuth::enabled()")]
+pub async fn get_quotes(
+    state: Data<AppState>,
+    params: FetchParams,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let limit = resolve_limit(params.limit, 10)?;
+    let sort = params
+        .sort
+        .as_deref()
+        .filter(|sort| {
+            matches!(
+                *sort,
+                "votes" | "hot" | "controversial" | "relevance" | "said_at"
+            )
+        })
+        .unwrap_or("date");
+    let sort_direction = params.sort_direction.is_some_and(|d| d);
+    let cursor = match params.cursor.as_deref().map(QuoteCursor::decode) {
+        Some(Ok(cursor)) => {
+            if cursor.sort != sort
+                || cursor.ascending != sort_direction
+                || cursor.filters != filter_hash(&params)
+                || !cursor.has_valid_key()
+            {
+                return Err(ApiError::bad_request(
+                    ErrorCode::CursorMismatch,
+                    "Cursor does not match this query.",
+                ));
+            }
+            Some(cursor)
+        }
+        Some(Err(_)) => {
+            return Err(ApiError::bad_request(
+                ErrorCode::InvalidCursor,
+                "Invalid cursor.",
+            ))
+        }
+        None => None,
+    };
+    let shards = fetch_quotes(
+        &state,
+        &params,
+        &user,
+        limit,
+        sort,
+        sort_direction,
+        cursor.as_ref(),
+    )
+    .await?;
+    let next_cursor = match shards.last() {
+        Some(last) if shards.iter().filter(|s| s.index == 1).count() as i64 == limit => {
+            QuoteCursor {
+                sort: sort.to_string(),
+                ascending: sort_direction,
+                key: last.sort_key.clone().unwrap_or_default(),
+                id: last.id,
+                filters: filter_hash(&params),
+            }
+            .encode()
+            .ok()
+        }
+        _ => None,
+    };
+    Ok(HttpResponse::Ok().json(QuotesResponse {
+        quotes: shards_to_quotes(shards.as_slice(), &state.ldap).await?,
+        next_cursor,
+    }))
+}
+
+/// Get a random quote matching the same filters as listing quotes
+#[utoipa::path(
+    tag = "quotes",
+    params(FetchParams),
+    responses(
+        (status = 200, body = QuoteResponse),
+        (status = 404, description = "No quote matches the filters", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/quotes/random", wrap = "CSHAuth::enabled()")]
+pub async fn get_random_quote(
+    state: Data<AppState>,
+    params: FetchParams,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let shards = fetch_quotes(&state, &params, &user, 1, "random", false, None).await?;
+    match shards_to_quotes(shards.as_slice(), &state.ldap)
+        .await?
+        .into_iter()
+        .next()
     {
-        Ok((_, shards)) => match shards_to_quotes(shards.as_slice(), &state.ldap).await {
-            Ok(quotes) => HttpResponse::Ok().json(quotes),
-            Err(response) => response,
-        },
-        Err(res) => res,
+        Some(quote) => Ok(HttpResponse::Ok().json(quote)),
+        None => Err(ApiError::not_found(
+            ErrorCode::QuoteNotFound,
+            "No quote matches the filters",
+        )),
     }
 }
 
+/// Get the quote of the day
+#[utoipa::path(
+    tag = "quotes",
+    responses(
+        (status = 200, body = QuoteResponse),
+        (status = 404, description = "There are no quotes to choose from", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/quotes/daily", wrap = "CSHAuth::enabled()")]
+pub async fn get_daily_quote(state: Data<AppState>, user: User) -> Result<HttpResponse, ApiError> {
+    // Eligible quotes are ordered by a hash of the day. Only quotes submitted
+    // before the day started count, scored by the votes cast before then, so
+    // everyone gets the same quote all day
+    let id = query!(
+        "WITH today AS (
+            SELECT day, (day::timestamp AT TIME ZONE $1)::timestamp AS start
+            FROM (SELECT (NOW() AT TIME ZONE $1)::date AS day) AS local
+        ), todays_votes AS (
+            SELECT quote_id, SUM(CASE WHEN vote = 'upvote' THEN 1 ELSE -1 END) AS score
+            FROM votes, today
+            WHERE votes.timestamp >= today.start
+            GROUP BY quote_id
+        )
+        SELECT q.id FROM quotes q
+        CROSS JOIN today
+        LEFT JOIN todays_votes v ON v.quote_id = q.id
+        WHERE q.timestamp < today.start
+        AND q.id NOT IN (SELECT quote_id FROM hidden)
+        AND q.id NOT IN (SELECT quote_id FROM pending_approvals)
+        AND q.score - COALESCE(v.score, 0) >= 0
+        ORDER BY md5(today.day::text || q.id::text)
+        LIMIT 1",
+        *DAILY_TIMEZONE,
+    )
+    .fetch_optional(&state.db)
+    .await?
+    .map(|row| row.id);
+    let Some(id) = id else {
+        return Err(ApiError::not_found(
+            ErrorCode::QuoteNotFound,
+            "There are no quotes to choose from",
+        ));
+    };
+
+    let shards = fetch_quotes_by_id(&state, &[id], &user).await?;
+    match shards_to_quotes(shards.as_slice(), &state.ldap)
+        .await?
+        .into_iter()
+        .next()
+    {
+        Some(quote) => Ok(HttpResponse::Ok().json(quote)),
+        None => Err(ApiError::not_found(
+            ErrorCode::QuoteNotFound,
+            "Quote could not be found",
+        )),
+    }
+}
+
+/// Replace the tags of a quote, as its submitter, one of its speakers or an admin
+#[utoipa::path(
+    tag = "tags",
+    request_body = Tags,
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Tags replaced"),
+        (status = 400, description = "Invalid tags, not your quote or quote does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[put("/quote/{id}/tags", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn tag_quote(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    user: User,
+    Json(body): Json<Tags>,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+    let tags = normalize_tags(&body.tags)?;
+
+    let mut transaction = state.db.begin().await?;
+
+    let allowed = query!(
+        "SELECT id FROM quotes q
+        WHERE q.id = $1
+        AND ($3 OR q.submitter = $2 OR $2 IN (SELECT speaker FROM shards WHERE quote_id = q.id))
+        FOR UPDATE",
+        id,
+        user.preferred_username,
+        user.has_role(Role::Moderator) || !*SECURITY_ENABLED,
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+    if allowed.is_none() {
+        return Err(ApiError::bad_request(
+            ErrorCode::TagEditNotAllowed,
+            "Either you are not the submitter or a speaker of this quote, or this quote does not exist.",
+        ));
+    }
+    replace_tags(id, &tags, &mut transaction).await?;
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().body(""))
+}
+
+/// List tags by how many visible quotes have them
+#[utoipa::path(
+    tag = "tags",
+    responses(
+        (status = 200, body = [TagCount]),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/tags", wrap = "CSHAuth::enabled()")]
+pub async fn get_tags(state: Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let tags = query_as!(
+        TagCount,
+        "SELECT tag, COUNT(*) AS \"count!\"
+        FROM quote_tags
+        WHERE quote_id NOT IN (SELECT quote_id FROM hidden)
+        AND quote_id NOT IN (SELECT quote_id FROM pending_approvals)
+        GROUP BY tag
+        ORDER BY COUNT(*) DESC, tag",
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+/// List members
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 200, body = [UserResponse]),
+    ),
+    security(("bearer" = [])),
+)]
 #[get("/users", wrap = "CSHAuth::enabled()")]
-pub async fn get_users(state: Data<AppState>) -> impl Responder {
-    match ldap::get_group_members(&state.ldap, "member").await {
-        Ok(users) => HttpResponse::Ok().json(
-            users
-                .into_iter()
-                .map(|x| UserResponse {
-                    uid: x.uid,
-                    cn: x.cn,
-                })
-                .collect::<Vec<_>>(),
-        ),
-        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+pub async fn get_users(state: Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let users = ldap::get_group_members(&state.ldap, "member")
+        .await
+        .map_err(ApiError::ldap)?;
+    Ok(HttpResponse::Ok().json(
+        users
+            .into_iter()
+            .map(|x| UserResponse {
+                uid: x.uid,
+                cn: x.cn,
+            })
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// Start of `window` as of `now`, or `None` for all time
+fn window_start(
+    window: LeaderboardWindow,
+    now: chrono::NaiveDateTime,
+) -> Option<chrono::NaiveDateTime> {
+    match window {
+        LeaderboardWindow::Week => Some(now - chrono::Duration::days(7)),
+        LeaderboardWindow::Month => Some(now - chrono::Duration::days(30)),
+        // Spring semesters count from January and fall semesters from August
+        LeaderboardWindow::Semester => {
+            let month = if now.month() >= 8 { 8 } else { 1 };
+            chrono::NaiveDate::from_ymd_opt(now.year(), month, 1)
+                .and_then(|day| day.and_hms_opt(0, 0, 0))
+        }
+        LeaderboardWindow::AllTime => None,
+    }
+}
+
+fn to_user_counts(counts: &[UserCount], names: &HashMap<String, String>) -> Vec<UserCountResponse> {
+    counts
+        .iter()
+        .filter_map(|x| {
+            Some(UserCountResponse {
+                user: to_user_response(&x.username, names)?,
+                count: x.count,
+            })
+        })
+        .collect()
+}
+
+/// Get the most quoted speakers, most prolific submitters, highest scoring
+/// quotes and most favorited quotes
+#[utoipa::path(
+    tag = "stats",
+    params(LeaderboardParams),
+    responses(
+        (status = 200, body = LeaderboardResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/stats/leaderboard", wrap = "CSHAuth::enabled()")]
+pub async fn get_leaderboard(
+    state: Data<AppState>,
+    params: web::Query<LeaderboardParams>,
+    user: User,
+) -> Result<HttpResponse, ApiError> {
+    let window = params.window.unwrap_or(LeaderboardWindow::AllTime);
+    let since = window_start(window, chrono::Utc::now().naive_utc());
+    let limit = params.limit.unwrap_or(10).clamp(1, MAX_LEADERBOARD_LIMIT);
+
+    let speakers = query_as!(
+        UserCount,
+        "SELECT speaker AS username, COUNT(DISTINCT quote_id) AS \"count!\"
+        FROM shards
+        WHERE quote_id IN (SELECT id FROM quotes WHERE $1::timestamp IS NULL OR timestamp >= $1)
+        AND quote_id NOT IN (SELECT quote_id FROM hidden)
+        AND quote_id NOT IN (SELECT quote_id FROM pending_approvals)
+        GROUP BY speaker
+        ORDER BY COUNT(DISTINCT quote_id) DESC, speaker
+        LIMIT $2",
+        since,
+        limit,
+    )
+    .fetch_all(&state.db)
+    .await?;
+    let submitters = query_as!(
+        UserCount,
+        "SELECT submitter AS username, COUNT(*) AS \"count!\"
+        FROM quotes
+        WHERE ($1::timestamp IS NULL OR timestamp >= $1)
+        AND id NOT IN (SELECT quote_id FROM hidden)
+        AND id NOT IN (SELECT quote_id FROM pending_approvals)
+        GROUP BY submitter
+        ORDER BY COUNT(*) DESC, submitter
+        LIMIT $2",
+        since,
+        limit,
+    )
+    .fetch_all(&state.db)
+    .await?;
+    let names = get_display_names(
+        speakers
+            .iter()
+            .chain(submitters.iter())
+            .map(|x| x.username.clone()),
+        &state.ldap,
+    )
+    .await?;
+
+    // Picked among quotes that are visible to everyone, so the caller's own
+    // hidden or pending quotes never show up
+    let top_ids: Vec<i32> = query!(
+        "SELECT id FROM quotes
+        WHERE ($1::timestamp IS NULL OR timestamp >= $1)
+        AND id NOT IN (SELECT quote_id FROM hidden)
+        AND id NOT IN (SELECT quote_id FROM pending_approvals)
+        ORDER BY score DESC, id DESC
+        LIMIT $2",
+        since,
+        limit,
+    )
+    .fetch_all(&state.db)
+    .await?
+    .into_iter()
+    .map(|x| x.id)
+    .collect();
+    let top_quotes = fetch_quotes_by_id(&state, &top_ids, &user).await?;
+    // Favorites aren't timestamped, so the window applies to when the quote
+    // was submitted
+    let favorites = query!(
+        "SELECT f.quote_id, COUNT(*) AS \"count!\"
+        FROM favorites f
+        JOIN quotes q ON q.id = f.quote_id
+        WHERE ($1::timestamp IS NULL OR q.timestamp >= $1)
+        AND q.id NOT IN (SELECT quote_id FROM hidden)
+        AND q.id NOT IN (SELECT quote_id FROM pending_approvals)
+        GROUP BY f.quote_id
+        ORDER BY COUNT(*) DESC, f.quote_id DESC
+        LIMIT $2",
+        since,
+        limit,
+    )
+    .fetch_all(&state.db)
+    .await?;
+    let favorited_ids: Vec<i32> = favorites.iter().map(|x| x.quote_id).collect();
+    let favorited = fetch_quotes_by_id(&state, &favorited_ids, &user).await?;
+    let favorites: HashMap<i32, i64> = favorites
+        .into_iter()
+        .map(|x| (x.quote_id, x.count))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(LeaderboardResponse {
+        window,
+        since,
+        speakers: to_user_counts(&speakers, &names),
+        submitters: to_user_counts(&submitters, &names),
+        top_quotes: shards_to_quotes(top_quotes.as_slice(), &state.ldap).await?,
+        most_favorited: shards_to_quotes(favorited.as_slice(), &state.ldap)
+            .await?
+            .into_iter()
+            .filter_map(|quote| {
+                let favorites = *favorites.get(&quote.id)?;
+                Some(FavoritedQuoteResponse { quote, favorites })
+            })
+            .collect(),
+    }))
+}
+
+/// Get how many quotes a user said and submitted, how many votes they cast
+/// and the score they received
+#[utoipa::path(
+    tag = "users",
+    params(
+        ("uid" = String, Path, description = "Username"),
+    ),
+    responses(
+        (status = 200, body = UserStatsResponse),
+        (status = 400, description = "Invalid username", body = ErrorResponse),
+        (status = 404, description = "User could not be found", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/users/{uid}/stats", wrap = "CSHAuth::enabled()")]
+pub async fn get_user_stats(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+) -> Result<HttpResponse, ApiError> {
+    let (uid,) = path.into_inner();
+    if !is_valid_username(&uid) {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidUsername,
+            "Invalid username format specified.",
+        ));
     }
+    let names = get_display_names(std::iter::once(uid.clone()), &state.ldap).await?;
+    let Some(user) = to_user_response(&uid, &names) else {
+        return Err(ApiError::not_found(
+            ErrorCode::UserNotFound,
+            "User could not be found",
+        ));
+    };
+
+    let stats = query!(
+        "SELECT
+            (
+                SELECT COUNT(*) FROM quotes
+                WHERE id IN (SELECT quote_id FROM shards WHERE speaker = $1)
+                AND id NOT IN (SELECT quote_id FROM hidden)
+                AND id NOT IN (SELECT quote_id FROM pending_approvals)
+            ) AS \"quotes_said!\",
+            (
+                SELECT COUNT(*) FROM quotes
+                WHERE submitter = $1
+                AND id NOT IN (SELECT quote_id FROM hidden)
+                AND id NOT IN (SELECT quote_id FROM pending_approvals)
+            ) AS \"quotes_submitted!\",
+            (SELECT COUNT(*) FROM votes WHERE submitter = $1) AS \"votes_cast!\",
+            (
+                SELECT COALESCE(SUM(score), 0)::int8 FROM quotes
+                WHERE id IN (SELECT quote_id FROM shards WHERE speaker = $1)
+                AND id NOT IN (SELECT quote_id FROM hidden)
+                AND id NOT IN (SELECT quote_id FROM pending_approvals)
+            ) AS \"score_received!\"",
+        uid,
+    )
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(UserStatsResponse {
+        user,
+        quotes_said: stats.quotes_said,
+        quotes_submitted: stats.quotes_submitted,
+        votes_cast: stats.votes_cast,
+        score_received: stats.score_received,
+    }))
 }
 
-#[get("/reports", wrap = "CSHAuth::admin_only()")]
-pub async fn get_reports(state: Data<AppState>) -> impl Responder {
-    match log_query_as(
+/// List unresolved reports
+#[utoipa::path(
+    tag = "moderation",
+    responses(
+        (status = 200, body = [ReportedQuoteResponse]),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/reports", wrap = "CSHAuth::require_role(Role::Moderator)")]
+pub async fn get_reports(state: Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let (_, reports) = log_query_as(
         query_as!(
             ReportedQuoteShard,
             "SELECT pq.id AS \"quote_id!\", pq.submitter AS \"quote_submitter!\",
@@ -722,26 +1965,72 @@ pub async fn get_reports(state: Data<AppState>) -> impl Responder {
         .await,
         None,
     )
-    .await
-    {
-        Ok((_, reports)) => HttpResponse::Ok().json(format_reports(reports.as_slice())),
-        Err(res) => res,
-    }
+    .await?;
+    Ok(HttpResponse::Ok().json(format_reports(reports.as_slice())))
 }
 
-impl From<sqlx::Error> for SqlxErrorOrResponse<'_> {
-    fn from(error: sqlx::Error) -> Self {
-        Self::SqlxError(error)
-    }
+/// List moderation log entries
+#[utoipa::path(
+    tag = "moderation",
+    params(ModerationLogParams),
+    responses(
+        (status = 200, body = [ModerationLogEntry]),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/moderation/log", wrap = "CSHAuth::admin_only()")]
+pub async fn get_moderation_log(
+    state: Data<AppState>,
+    params: web::Query<ModerationLogParams>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = resolve_limit(params.limit, 50)?;
+
+    let entries = query_as!(
+        ModerationLogEntry,
+        "SELECT id, actor, action AS \"action: ModerationAction\", quote_id, details, timestamp
+        FROM moderation_log
+        WHERE ($1::varchar IS NULL OR actor = $1)
+        AND ($2::int4 IS NULL OR quote_id = $2)
+        AND ($3::moderation_action IS NULL OR action = $3)
+        AND ($4::timestamp IS NULL OR timestamp >= $4)
+        AND ($5::timestamp IS NULL OR timestamp < $5)
+        AND ($6::int4 IS NULL OR id < $6)
+        ORDER BY id DESC
+        LIMIT $7",
+        params.actor,
+        params.quote_id,
+        params.action.clone() as Option<ModerationAction>,
+        params.after,
+        params.before,
+        params.lt,
+        limit,
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(entries))
 }
 
-#[put("/quote/{id}/resolve", wrap = "CSHAuth::admin_only()")]
+/// Resolve a quote's reports
+#[utoipa::path(
+    tag = "moderation",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+        ResolveParams,
+    ),
+    responses(
+        (status = 200, description = "Reports resolved"),
+        (status = 400, description = "Reports already resolved or do not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[put("/quote/{id}/resolve", wrap = "CSHAuth::require_role(Role::Moderator)")]
 pub async fn resolve_report(
     state: Data<AppState>,
     path: Path<(i32,)>,
     user: User,
     params: web::Query<ResolveParams>,
-) -> Result<HttpResponse, SqlxErrorOrResponse<'static>> {
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
 
     state.db.acquire().await?.transaction(|transaction| Box::pin(async move {
@@ -756,13 +2045,22 @@ pub async fn resolve_report(
                 Ok(result) => result,
                 Err(sqlx::Error::RowNotFound) =>
                 {
-                    return Err(SqlxErrorOrResponse::Response(StatusCode::BAD_REQUEST, "Report is either already resolved or doesn't exist."));
+                    return Err(ApiError::bad_request(ErrorCode::NoOpenReports, "Report is either already resolved or doesn't exist."));
                 },
                 Err(err) => return Err(err.into()),
             };
 
         log!(Level::Trace, "resolved all quote's reports");
 
+        log_moderation(
+            &mut **transaction,
+            &user.preferred_username,
+            ModerationAction::Resolve,
+            Some(id),
+            None,
+        )
+        .await?;
+
         if let Some(true) = params.hide {
             hide_quote_by_id(id, user, result.reason, &mut *transaction).await?;
         }
@@ -774,23 +2072,35 @@ pub async fn resolve_report(
     Ok(HttpResponse::Ok().body(""))
 }
 
-#[post("/quote/{id}/favorite", wrap = "CSHAuth::enabled()")]
+/// Favorite a quote
+#[utoipa::path(
+    tag = "favorites",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote favorited"),
+        (status = 400, description = "Already favorited or quote does not exist", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[post("/quote/{id}/favorite", wrap = "CSHAuth::require_role(Role::Member)")]
 pub async fn favorite_quote(
     state: Data<AppState>,
     user: User,
     path: Path<(i32,)>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
 
-    let mut transaction = match open_transaction(&state.db).await {
-        Ok(t) => t,
-        Err(res) => return res,
-    };
+    let mut transaction = open_transaction(&state.db).await?;
 
-    match log_query(
+    let (tx, result) = log_query(
         query!(
             "INSERT INTO favorites (quote_id, username)
-            VALUES ($1, $2)",
+            SELECT $1, $2
+            WHERE $1 IN (SELECT id FROM quotes)
+            AND $1 NOT IN (SELECT quote_id FROM pending_approvals)
+            ON CONFLICT DO NOTHING",
             id,
             user.preferred_username,
         )
@@ -798,41 +2108,42 @@ pub async fn favorite_quote(
         .await,
         Some(transaction),
     )
-    .await
-    {
-        Ok((tx, result)) => {
-            transaction = tx.unwrap();
-            if result.rows_affected() == 0 {
-                return HttpResponse::BadRequest()
-                    .body("Quote is either already favorited or doesn't exist.");
-            }
-        }
-        Err(res) => return res,
+    .await?;
+    transaction = tx.unwrap();
+    if result.rows_affected() == 0 {
+        return Err(ApiError::bad_request(
+            ErrorCode::AlreadyFavorited,
+            "Quote is either already favorited or doesn't exist.",
+        ));
     }
 
-    match transaction.commit().await {
-        Ok(_) => HttpResponse::Ok().body(""),
-        Err(e) => {
-            log!(Level::Error, "Transaction failed to commit");
-            HttpResponse::InternalServerError().body(e.to_string())
-        }
-    }
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().body(""))
 }
 
-#[delete("/quote/{id}/favorite", wrap = "CSHAuth::enabled()")]
+/// Unfavorite a quote
+#[utoipa::path(
+    tag = "favorites",
+    params(
+        ("id" = i32, Path, description = "Quote id"),
+    ),
+    responses(
+        (status = 200, description = "Quote unfavorited"),
+        (status = 400, description = "Quote is not favorited", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[delete("/quote/{id}/favorite", wrap = "CSHAuth::require_role(Role::Member)")]
 pub async fn unfavorite_quote(
     state: Data<AppState>,
     user: User,
     path: Path<(i32,)>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     let (id,) = path.into_inner();
 
-    let mut transaction = match open_transaction(&state.db).await {
-        Ok(t) => t,
-        Err(res) => return res,
-    };
+    let mut transaction = open_transaction(&state.db).await?;
 
-    match log_query(
+    let (tx, result) = log_query(
         query!(
             "DELETE FROM favorites WHERE quote_id=$1 AND username=$2",
             id,
@@ -842,26 +2153,27 @@ pub async fn unfavorite_quote(
         .await,
         Some(transaction),
     )
-    .await
-    {
-        Ok((tx, result)) => {
-            transaction = tx.unwrap();
-            if result.rows_affected() == 0 {
-                return HttpResponse::BadRequest().body("Quote is not favorited.");
-            }
-        }
-        Err(res) => return res,
+    .await?;
+    transaction = tx.unwrap();
+    if result.rows_affected() == 0 {
+        return Err(ApiError::bad_request(
+            ErrorCode::NotFavorited,
+            "Quote is not favorited.",
+        ));
     }
 
-    match transaction.commit().await {
-        Ok(_) => HttpResponse::Ok().body(""),
-        Err(e) => {
-            log!(Level::Error, "Transaction failed to commit");
-            HttpResponse::InternalServerError().body(e.to_string())
-        }
-    }
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().body(""))
 }
 
+/// Get build information
+#[utoipa::path(
+    tag = "meta",
+    responses(
+        (status = 200, body = VersionResponse),
+    ),
+    security(("bearer" = [])),
+)]
 #[get("/version", wrap = "CSHAuth::enabled()")]
 pub async fn get_version() -> impl Responder {
     HttpResponse::Ok().json(VersionResponse {
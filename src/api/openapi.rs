@@ -0,0 +1,139 @@
+use actix_web::{get, HttpResponse, Responder, ResponseError};
+use log::{log, Level};
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{
+    api::{endpoints, error::ApiError, settings, tokens},
+    schema::{
+        api::{
+            ErrorCode, ErrorResponse, FavoritedQuoteResponse, Hidden, HideEventResponse,
+            LeaderboardResponse, LeaderboardWindow, MatchMode, NewApiToken, NewApiTokenResponse,
+            NewQuote, NewQuoteShard, OptOut, QuoteResponse, QuoteRevisionResponse,
+            QuoteShardResponse, QuotesResponse, Reason, ReportResponse, ReportedQuoteResponse,
+            Tags, UserCountResponse, UserResponse, UserSettings, UserStatsResponse,
+            VersionResponse,
+        },
+        db::{
+            ApiToken, ApiTokenScope, HideAction, ModerationAction, ModerationLogEntry, QuoteVote,
+            TagCount, Vote,
+        },
+    },
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Quotefault API"),
+    servers((url = "/api")),
+    paths(
+        endpoints::create_quote,
+        endpoints::get_quotes,
+        endpoints::get_random_quote,
+        endpoints::get_daily_quote,
+        endpoints::get_quote,
+        endpoints::approve_quote,
+        endpoints::reject_quote,
+        endpoints::tag_quote,
+        endpoints::get_tags,
+        endpoints::edit_quote,
+        endpoints::get_quote_history,
+        endpoints::delete_quote,
+        endpoints::hide_quote,
+        endpoints::unhide_quote,
+        endpoints::get_hide_history,
+        endpoints::report_quote,
+        endpoints::resolve_report,
+        endpoints::get_reports,
+        endpoints::get_moderation_log,
+        endpoints::vote_quote,
+        endpoints::unvote_quote,
+        endpoints::get_quote_votes,
+        endpoints::favorite_quote,
+        endpoints::unfavorite_quote,
+        endpoints::get_users,
+        endpoints::get_user_stats,
+        endpoints::get_leaderboard,
+        endpoints::get_version,
+        tokens::get_api_tokens,
+        tokens::create_api_token,
+        tokens::revoke_api_token,
+        settings::get_settings,
+        settings::update_settings,
+        settings::set_user_opt_out,
+    ),
+    components(schemas(
+        NewQuote,
+        NewQuoteShard,
+        Reason,
+        QuoteResponse,
+        QuotesResponse,
+        QuoteShardResponse,
+        QuoteRevisionResponse,
+        Hidden,
+        HideEventResponse,
+        UserResponse,
+        ReportedQuoteResponse,
+        ReportResponse,
+        VersionResponse,
+        LeaderboardWindow,
+        LeaderboardResponse,
+        UserCountResponse,
+        FavoritedQuoteResponse,
+        UserStatsResponse,
+        ModerationLogEntry,
+        Vote,
+        HideAction,
+        ModerationAction,
+        ErrorResponse,
+        ErrorCode,
+        NewApiToken,
+        NewApiTokenResponse,
+        ApiToken,
+        ApiTokenScope,
+        UserSettings,
+        OptOut,
+        Tags,
+        TagCount,
+        MatchMode,
+        QuoteVote,
+    )),
+    modifiers(&BearerAuth),
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[get("/openapi.json")]
+pub async fn get_openapi_json() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+#[get("/openapi.yaml")]
+pub async fn get_openapi_yaml() -> impl Responder {
+    match ApiDoc::openapi().to_yaml() {
+        Ok(yaml) => HttpResponse::Ok()
+            .content_type("application/yaml")
+            .body(yaml),
+        Err(err) => {
+            log!(Level::Error, "Failed to serialize OpenAPI spec: {err}");
+            ApiError::internal().error_response()
+        }
+    }
+}
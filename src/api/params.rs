@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use actix_web::{dev::Payload, error::QueryPayloadError, web::Query, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+
+use crate::{api::error::ApiError, schema::api::FetchParams};
+
+/// Filters of `FetchParams` that may be given more than once, e.g.
+/// `speaker=a&speaker=b`
+const REPEATABLE: [&str; 3] = ["speaker", "submitter", "involved"];
+
+/// `Query` rejects repeated keys, so repeatable filters are collected before
+/// the rest of the query string is deserialized.
+pub(crate) fn parse_fetch_params(query_string: &str) -> Result<FetchParams, QueryPayloadError> {
+    let mut repeated: HashMap<String, Vec<String>> = HashMap::new();
+    let mut rest = form_urlencoded::Serializer::new(String::new());
+    for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+        if REPEATABLE.contains(&key.as_ref()) {
+            let value = value.trim();
+            if !value.is_empty() {
+                repeated
+                    .entry(key.into_owned())
+                    .or_default()
+                    .push(value.to_string());
+            }
+        } else {
+            rest.append_pair(&key, &value);
+        }
+    }
+
+    let mut params = Query::<FetchParams>::from_query(&rest.finish())?.into_inner();
+    let mut take = |key: &str| {
+        let mut values = repeated.remove(key).unwrap_or_default();
+        values.sort();
+        values.dedup();
+        values
+    };
+    params.speaker = take("speaker");
+    params.submitter = take("submitter");
+    params.involved = take("involved");
+    Ok(params)
+}
+
+impl FromRequest for FetchParams {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(parse_fetch_params(req.query_string()).map_err(|err| {
+            ApiError::invalid_request("Invalid query parameters.")
+                .with_details(err.to_string().into())
+                .into()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::api::MatchMode;
+
+    #[test]
+    fn collects_repeated_filters() {
+        let params = parse_fetch_params(
+            "speaker=bob&limit=5&speaker=alice&speaker=bob&involved=a%25_&submitter=&match=all",
+        )
+        .unwrap();
+        assert_eq!(params.speaker, vec!["alice", "bob"]);
+        assert_eq!(params.involved, vec!["a%_"]);
+        assert!(params.submitter.is_empty());
+        assert_eq!(params.limit, Some(5));
+        assert_eq!(params.match_mode, Some(MatchMode::All));
+
+        assert!(parse_fetch_params("limit=5&limit=6").is_err());
+        assert!(parse_fetch_params("match=some").is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use actix_web::{
+    get, put,
+    web::{Data, Json, Path, Query},
+    HttpResponse,
+};
+use log::{log, Level};
+use sqlx::{query, query_as, Postgres, Transaction};
+
+use crate::{
+    api::{db::log_moderation, error::ApiError},
+    app::AppState,
+    auth::{roles::Role, CSHAuth, User},
+    schema::{
+        api::{ErrorCode, OptOut, OptOutParams, UserSettings},
+        db::ModerationAction,
+    },
+    utils::is_valid_username,
+};
+
+const OPT_OUT_REASON: &str = "Speaker opted out of being quoted";
+
+/// Hides every visible quote `speaker` is quoted in, returning their ids
+async fn hide_quotes_of_speaker(
+    speaker: &str,
+    actor: &str,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<i32>, ApiError> {
+    let hidden = query!(
+        "INSERT INTO hide_events (quote_id, action, reason, actor)
+        SELECT DISTINCT quote_id, 'hide'::hide_action, $2, $3::varchar FROM shards
+        WHERE speaker = $1 AND quote_id NOT IN (SELECT quote_id FROM hidden)
+        RETURNING quote_id",
+        speaker,
+        OPT_OUT_REASON,
+        actor,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+    log!(
+        Level::Info,
+        "{actor} hid {} quotes of {speaker} after opting out",
+        hidden.len()
+    );
+    Ok(hidden.into_iter().map(|x| x.quote_id).collect())
+}
+
+/// Get your settings
+#[utoipa::path(
+    tag = "settings",
+    responses(
+        (status = 200, body = UserSettings),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/settings", wrap = "CSHAuth::enabled()")]
+pub async fn get_settings(state: Data<AppState>, user: User) -> Result<HttpResponse, ApiError> {
+    let settings = query_as!(
+        UserSettings,
+        "SELECT COALESCE(s.require_approval, FALSE) AS \"require_approval!\",
+            COALESCE(s.opt_out, FALSE) AS \"opt_out!\"
+        FROM (SELECT $1::varchar AS username) u
+        LEFT JOIN user_settings s ON s.username = u.username",
+        user.preferred_username,
+    )
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+/// Update your settings
+///
+/// Requiring approval only applies to quotes submitted afterwards. Opting out
+/// stops anyone from quoting you, and can also hide every existing quote of you.
+#[utoipa::path(
+    tag = "settings",
+    request_body = UserSettings,
+    params(OptOutParams),
+    responses(
+        (status = 200, body = UserSettings),
+    ),
+    security(("bearer" = [])),
+)]
+#[put("/settings", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn update_settings(
+    state: Data<AppState>,
+    user: User,
+    params: Query<OptOutParams>,
+    Json(settings): Json<UserSettings>,
+) -> Result<HttpResponse, ApiError> {
+    let mut transaction = state.db.begin().await?;
+
+    query!(
+        "INSERT INTO user_settings (username, require_approval, opt_out)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (username) DO UPDATE SET require_approval = $2, opt_out = $3",
+        user.preferred_username,
+        settings.require_approval,
+        settings.opt_out,
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    if settings.opt_out && params.hide_existing.unwrap_or(false) {
+        hide_quotes_of_speaker(
+            &user.preferred_username,
+            &user.preferred_username,
+            &mut transaction,
+        )
+        .await?;
+    }
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+/// Opt a user in or out of being quoted
+#[utoipa::path(
+    tag = "moderation",
+    request_body = OptOut,
+    params(
+        ("uid" = String, Path, description = "Username"),
+        OptOutParams,
+    ),
+    responses(
+        (status = 200, description = "Opt-out updated"),
+        (status = 400, description = "Invalid username", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[put("/users/{uid}/opt-out", wrap = "CSHAuth::admin_only()")]
+pub async fn set_user_opt_out(
+    state: Data<AppState>,
+    user: User,
+    path: Path<(String,)>,
+    params: Query<OptOutParams>,
+    Json(body): Json<OptOut>,
+) -> Result<HttpResponse, ApiError> {
+    let (uid,) = path.into_inner();
+    if !is_valid_username(&uid) {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidUsername,
+            "Invalid username format specified.",
+        ));
+    }
+
+    let mut transaction = state.db.begin().await?;
+
+    query!(
+        "INSERT INTO user_settings (username, opt_out)
+        VALUES ($1, $2)
+        ON CONFLICT (username) DO UPDATE SET opt_out = $2",
+        uid,
+        body.opt_out,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    log_moderation(
+        &mut *transaction,
+        &user.preferred_username,
+        ModerationAction::OptOut,
+        None,
+        Some(format!(
+            "opted {uid} {}",
+            if body.opt_out { "out" } else { "in" }
+        )),
+    )
+    .await?;
+
+    if body.opt_out && params.hide_existing.unwrap_or(false) {
+        for id in hide_quotes_of_speaker(&uid, &user.preferred_username, &mut transaction).await? {
+            log_moderation(
+                &mut *transaction,
+                &user.preferred_username,
+                ModerationAction::Hide,
+                Some(id),
+                Some(OPT_OUT_REASON.to_string()),
+            )
+            .await?;
+        }
+    }
+
+    transaction.commit().await?;
+    Ok(HttpResponse::Ok().body(""))
+}
@@ -0,0 +1,150 @@
+use actix_web::{
+    delete, get, post,
+    web::{Data, Json, Path},
+    HttpResponse,
+};
+use log::{log, Level};
+use serde_json::json;
+use sqlx::{query, query_as};
+
+use crate::{
+    api::error::ApiError,
+    app::AppState,
+    auth::{api_token, roles::Role, CSHAuth, User},
+    schema::{
+        api::{ErrorCode, NewApiToken, NewApiTokenResponse},
+        db::{ApiToken, ApiTokenScope},
+    },
+};
+
+const MAX_TOKEN_LIFETIME_DAYS: i64 = 365;
+
+/// List your API tokens
+#[utoipa::path(
+    tag = "tokens",
+    responses(
+        (status = 200, body = [ApiToken]),
+    ),
+    security(("bearer" = [])),
+)]
+#[get("/tokens", wrap = "CSHAuth::enabled()")]
+pub async fn get_api_tokens(state: Data<AppState>, user: User) -> Result<HttpResponse, ApiError> {
+    let tokens = query_as!(
+        ApiToken,
+        "SELECT id, name, scope AS \"scope: ApiTokenScope\", created, expires, last_used
+        FROM api_tokens
+        WHERE owner = $1
+        ORDER BY id DESC",
+        user.preferred_username,
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(HttpResponse::Ok().json(tokens))
+}
+
+/// Create an API token
+///
+/// The token can act with at most the role its scope allows, and never above
+/// your own role. It is only shown once.
+#[utoipa::path(
+    tag = "tokens",
+    request_body = NewApiToken,
+    responses(
+        (status = 200, body = NewApiTokenResponse),
+        (status = 400, description = "Invalid name or expiry, or scope above your role", body = ErrorResponse),
+        (status = 403, description = "Tokens can only be created with SSO", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[post("/tokens", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn create_api_token(
+    state: Data<AppState>,
+    user: User,
+    Json(body): Json<NewApiToken>,
+) -> Result<HttpResponse, ApiError> {
+    if user.is_api_token() {
+        return Err(ApiError::forbidden());
+    }
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > 64 {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidTokenName,
+            "Token name must be between 1 and 64 characters.",
+        ));
+    }
+    let now = chrono::Utc::now().naive_utc();
+    if body.expires <= now || body.expires > now + chrono::Duration::days(MAX_TOKEN_LIFETIME_DAYS) {
+        return Err(ApiError::bad_request(
+            ErrorCode::InvalidExpiry,
+            "Token must expire in the future and within 365 days.",
+        )
+        .with_details(json!({ "max_days": MAX_TOKEN_LIFETIME_DAYS })));
+    }
+    if !user.has_role(body.scope.max_role()) {
+        return Err(ApiError::bad_request(
+            ErrorCode::ScopeNotAllowed,
+            "Token scope exceeds your role.",
+        ));
+    }
+
+    let (token, token_hash) = api_token::generate();
+    let id = query!(
+        "INSERT INTO api_tokens (owner, name, token_hash, scope, expires)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id",
+        user.preferred_username,
+        name,
+        token_hash,
+        body.scope as ApiTokenScope,
+        body.expires,
+    )
+    .fetch_one(&state.db)
+    .await?
+    .id;
+
+    log!(
+        Level::Info,
+        "{} created API token {id} with scope {:?}",
+        user.preferred_username,
+        body.scope
+    );
+    Ok(HttpResponse::Ok().json(NewApiTokenResponse { id, token }))
+}
+
+/// Revoke an API token
+#[utoipa::path(
+    tag = "tokens",
+    params(
+        ("id" = i32, Path, description = "Token id"),
+    ),
+    responses(
+        (status = 200, description = "Token revoked"),
+        (status = 404, description = "Token does not exist or is not yours", body = ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+#[delete("/tokens/{id}", wrap = "CSHAuth::require_role(Role::Member)")]
+pub async fn revoke_api_token(
+    state: Data<AppState>,
+    user: User,
+    path: Path<(i32,)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id,) = path.into_inner();
+
+    let result = query!(
+        "DELETE FROM api_tokens WHERE id = $1 AND owner = $2",
+        id,
+        user.preferred_username,
+    )
+    .execute(&state.db)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found(
+            ErrorCode::TokenNotFound,
+            "Token does not exist.",
+        ));
+    }
+
+    Ok(HttpResponse::Ok().body(""))
+}
@@ -1,15 +1,27 @@
 use std::env;
 
-use actix_web::web::{self, scope, Data};
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    web::{self, scope, Data, JsonConfig, PathConfig, QueryConfig},
+    Error, HttpRequest,
+};
+use log::{log, Level};
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
 
 use crate::{
+    api::dev::{get_dev_jwks, mint_dev_token},
     api::endpoints::{
-        create_quote, delete_quote, favorite_quote, get_quote, get_quotes, get_reports, get_users,
-        get_version, hide_quote, report_quote, resolve_report, unfavorite_quote, unvote_quote,
-        vote_quote,
+        approve_quote, create_quote, delete_quote, edit_quote, favorite_quote, get_daily_quote,
+        get_hide_history, get_leaderboard, get_moderation_log, get_quote, get_quote_history,
+        get_quote_votes, get_quotes, get_random_quote, get_reports, get_tags, get_user_stats,
+        get_users, get_version, hide_quote, reject_quote, report_quote, resolve_report, tag_quote,
+        unfavorite_quote, unhide_quote, unvote_quote, vote_quote,
     },
-    auth::SECURITY_ENABLED,
+    api::error::ApiError,
+    api::openapi::{get_openapi_json, get_openapi_yaml},
+    api::settings::{get_settings, set_user_opt_out, update_settings},
+    api::tokens::{create_api_token, get_api_tokens, revoke_api_token},
+    auth::{DEV_AUTH, DEV_KEY, JWKS, SECURITY_ENABLED},
     ldap::client::LdapClient,
 };
 
@@ -28,24 +40,71 @@ pub fn configure_app(cfg: &mut web::ServiceConfig) {
         actix_cors::Cors::permissive()
     };
 
-    cfg.service(
-        scope("/api")
-            .wrap(cors)
-            .service(create_quote)
-            .service(get_quotes)
-            .service(get_users)
-            .service(get_quote)
-            .service(get_reports)
-            .service(delete_quote)
-            .service(hide_quote)
-            .service(report_quote)
-            .service(resolve_report)
-            .service(vote_quote)
-            .service(unvote_quote)
-            .service(get_version)
-            .service(favorite_quote)
-            .service(unfavorite_quote),
-    );
+    let api = scope("/api")
+        .wrap(cors)
+        .app_data(JsonConfig::default().error_handler(json_error))
+        .app_data(QueryConfig::default().error_handler(query_error))
+        .app_data(PathConfig::default().error_handler(path_error))
+        .service(create_quote)
+        .service(get_quotes)
+        .service(get_random_quote)
+        .service(get_daily_quote)
+        .service(get_users)
+        .service(get_user_stats)
+        .service(get_leaderboard)
+        .service(get_quote)
+        .service(approve_quote)
+        .service(reject_quote)
+        .service(tag_quote)
+        .service(get_tags)
+        .service(edit_quote)
+        .service(get_quote_history)
+        .service(get_reports)
+        .service(get_moderation_log)
+        .service(delete_quote)
+        .service(hide_quote)
+        .service(unhide_quote)
+        .service(get_hide_history)
+        .service(report_quote)
+        .service(resolve_report)
+        .service(vote_quote)
+        .service(unvote_quote)
+        .service(get_quote_votes)
+        .service(get_version)
+        .service(favorite_quote)
+        .service(unfavorite_quote)
+        .service(get_api_tokens)
+        .service(create_api_token)
+        .service(revoke_api_token)
+        .service(get_settings)
+        .service(update_settings)
+        .service(set_user_opt_out)
+        .service(get_openapi_json)
+        .service(get_openapi_yaml);
+
+    if *DEV_AUTH {
+        cfg.service(api.service(get_dev_jwks).service(mint_dev_token));
+    } else {
+        cfg.service(api);
+    }
+}
+
+fn json_error(err: JsonPayloadError, _req: &HttpRequest) -> Error {
+    ApiError::invalid_request("Invalid request body.")
+        .with_details(err.to_string().into())
+        .into()
+}
+
+fn query_error(err: QueryPayloadError, _req: &HttpRequest) -> Error {
+    ApiError::invalid_request("Invalid query parameters.")
+        .with_details(err.to_string().into())
+        .into()
+}
+
+fn path_error(err: PathError, _req: &HttpRequest) -> Error {
+    ApiError::invalid_request("Invalid path parameters.")
+        .with_details(err.to_string().into())
+        .into()
 }
 
 pub async fn get_app_data() -> Data<AppState> {
@@ -67,5 +126,13 @@ pub async fn get_app_data() -> Data<AppState> {
             .as_str(),
     )
     .await;
+    if *DEV_AUTH {
+        log!(
+            Level::Warn,
+            "Dev auth is enabled: tokens are signed by a local key and can be minted at /api/dev/token"
+        );
+        lazy_static::initialize(&DEV_KEY);
+    }
+    JWKS.spawn_refresh();
     Data::new(AppState { db, ldap })
 }
@@ -0,0 +1,87 @@
+use base64::{engine::general_purpose, Engine as _};
+use rand::RngCore;
+use sqlx::{query, Pool, Postgres};
+
+use super::{hash_token, roles::Role};
+use crate::schema::db::ApiTokenScope;
+
+/// Distinguishes API tokens from JWTs, which always start with `eyJ`
+pub const TOKEN_PREFIX: &str = "qf_";
+pub(super) const TOKEN_TYPE: &str = "ApiToken";
+
+impl ApiTokenScope {
+    /// Highest role a token with this scope can act as
+    pub fn max_role(&self) -> Role {
+        match self {
+            Self::Read => Role::Viewer,
+            Self::Write => Role::Member,
+            Self::Moderate => Role::Moderator,
+        }
+    }
+}
+
+pub fn is_api_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+/// Returns a new token and its hash
+pub fn generate() -> (String, Vec<u8>) {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = format!(
+        "{TOKEN_PREFIX}{}",
+        general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    );
+    let hash = hash_token(&token);
+    (token, hash)
+}
+
+/// Looks up an unexpired token, bumping its last used timestamp if it is
+/// over a minute old. Returns the owner and the highest role the token's
+/// scope allows.
+pub async fn lookup(
+    db: &Pool<Postgres>,
+    token: &str,
+) -> Result<Option<(String, Role)>, sqlx::Error> {
+    let row = query!(
+        "WITH token AS (
+            SELECT id, owner, scope FROM api_tokens
+            WHERE token_hash = $1 AND expires > NOW()
+        ), used AS (
+            UPDATE api_tokens SET last_used = NOW()
+            WHERE id IN (SELECT id FROM token)
+            AND (last_used IS NULL OR last_used < NOW() - INTERVAL '1 minute')
+        )
+        SELECT owner AS \"owner!\", scope AS \"scope!: ApiTokenScope\" FROM token",
+        hash_token(token),
+    )
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(|row| (row.owner, row.scope.max_role())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::User;
+
+    #[test]
+    fn generated_tokens_are_prefixed_and_hashed() {
+        let (token, token_hash) = generate();
+        assert!(is_api_token(&token));
+        assert_eq!(token_hash, hash_token(&token));
+        assert_ne!(generate().0, token);
+    }
+
+    #[test]
+    fn read_tokens_act_as_viewers() {
+        // eboard maps to admin by default
+        let user = User::from_api_token(
+            "cole".to_string(),
+            vec!["eboard".to_string()],
+            ApiTokenScope::Read.max_role(),
+        );
+        assert_eq!(user.role(), Role::Viewer);
+        assert!(!user.has_role(Role::Member));
+    }
+}
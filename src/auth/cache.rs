@@ -0,0 +1,66 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use super::User;
+
+const MAX_ENTRIES: usize = 4096;
+
+/// Verified JWTs keyed by the hash of the raw token, so repeat requests with
+/// the same token skip signature verification and role resolution until the
+/// token expires.
+pub struct TokenCache {
+    entries: RwLock<HashMap<Vec<u8>, User>>,
+}
+
+impl TokenCache {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, hash: &[u8], now: i64) -> Option<User> {
+        self.entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(hash)
+            .filter(|user| i64::from(user.exp) > now)
+            .cloned()
+    }
+
+    pub fn insert(&self, hash: Vec<u8>, user: User, now: i64) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, user| i64::from(user.exp) > now);
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+        entries.insert(hash, user);
+    }
+}
+
+impl Default for TokenCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::roles::Role;
+
+    #[test]
+    fn entries_expire_with_the_token() {
+        let cache = TokenCache::new();
+        let mut user = User::from_api_token("cole".to_string(), vec![], Role::Viewer);
+        user.exp = 1_000;
+        cache.insert(vec![1], user, 900);
+        assert_eq!(
+            cache.get(&[1], 999).map(|user| user.preferred_username),
+            Some("cole".to_string())
+        );
+        assert!(cache.get(&[1], 1_000).is_none());
+        assert!(cache.get(&[2], 999).is_none());
+    }
+}
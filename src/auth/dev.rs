@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use openssl::{
+    hash::MessageDigest,
+    pkey::{PKey, Private, Public},
+    rsa::Rsa,
+    sign::Signer,
+};
+use serde_json::{json, Value};
+
+use super::{TokenHeader, User, TOKEN_VALIDATION};
+
+const DEV_KID: &str = "quotefault-dev";
+const DEV_ISSUER: &str = "quotefault-dev";
+const DEV_AUDIENCE: &str = "quotefault";
+
+/// Keypair generated on startup in dev auth mode, used to mint and verify
+/// local tokens instead of CSH SSO.
+pub struct DevKey {
+    private: PKey<Private>,
+    public: PKey<Public>,
+}
+
+impl DevKey {
+    pub fn generate() -> Result<Self> {
+        let rsa = Rsa::generate(2048)?;
+        let public = Rsa::from_public_components(rsa.n().to_owned()?, rsa.e().to_owned()?)?;
+        Ok(Self {
+            private: PKey::from_rsa(rsa)?,
+            public: PKey::from_rsa(public)?,
+        })
+    }
+
+    pub fn public_keys(&self) -> HashMap<String, PKey<Public>> {
+        HashMap::from([(DEV_KID.to_string(), self.public.clone())])
+    }
+
+    pub fn jwks(&self) -> Result<Value> {
+        let rsa = self.public.rsa()?;
+        Ok(json!({
+            "keys": [{
+                "kid": DEV_KID,
+                "kty": "RSA",
+                "alg": "RS256",
+                "use": "sig",
+                "n": general_purpose::URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+                "e": general_purpose::URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+            }]
+        }))
+    }
+
+    /// Signs a token that passes `TOKEN_VALIDATION` for `username` with `groups`.
+    pub fn mint(
+        &self,
+        username: &str,
+        groups: Vec<String>,
+        name: Option<String>,
+        ttl: i64,
+    ) -> Result<(String, i64)> {
+        let now = chrono::Utc::now().timestamp();
+        let expires = now + ttl;
+        let user = User {
+            exp: expires as u32,
+            iat: now as u32,
+            nbf: None,
+            auth_time: Some(now as u32),
+            jti: format!("{username}-{now}"),
+            iss: TOKEN_VALIDATION
+                .issuer
+                .clone()
+                .unwrap_or(DEV_ISSUER.to_string()),
+            aud: vec![DEV_AUDIENCE.to_string()],
+            sub: username.to_string(),
+            typ: "Bearer".to_string(),
+            azp: TOKEN_VALIDATION
+                .audiences
+                .first()
+                .cloned()
+                .unwrap_or(DEV_AUDIENCE.to_string()),
+            nonce: None,
+            session_state: None,
+            scope: "openid profile email".to_string(),
+            sid: None,
+            email_verified: true,
+            name: name.clone(),
+            groups,
+            preferred_username: username.to_string(),
+            given_name: name,
+            family_name: None,
+            email: Some(format!("{username}@csh.rit.edu")),
+            role: Default::default(),
+        };
+        let header = TokenHeader {
+            alg: "RS256".to_string(),
+            kid: DEV_KID.to_string(),
+            typ: "JWT".to_string(),
+        };
+
+        let header_64 = general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?);
+        let payload_64 = general_purpose::URL_SAFE_NO_PAD.encode(serde_json::to_vec(&user)?);
+        let mut signer = Signer::new(MessageDigest::sha256(), &self.private)?;
+        signer.update(header_64.as_bytes())?;
+        signer.update(b".")?;
+        signer.update(payload_64.as_bytes())?;
+        let signature = general_purpose::URL_SAFE_NO_PAD.encode(signer.sign_to_vec()?);
+        Ok((format!("{header_64}.{payload_64}.{signature}"), expires))
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    collections::HashMap,
+    env,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use futures::lock::Mutex;
+use isahc::{config::Configurable, AsyncReadResponseExt, Request, RequestExt};
+use log::{log, Level};
+use openssl::{
+    bn::BigNum,
+    pkey::{PKey, Public},
+    rsa::Rsa,
+};
+use serde::Deserialize;
+
+use super::token::DEFAULT_ISSUER;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const MIN_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize, Debug)]
+struct CertKey {
+    kid: String,
+    kty: String,
+    r#use: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CertData {
+    keys: Vec<CertKey>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OpenIdConfiguration {
+    issuer: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum JwksSource {
+    Url(String),
+    Issuer(String),
+    Static,
+}
+
+pub struct JwksCache {
+    source: JwksSource,
+    keys: RwLock<HashMap<String, PKey<Public>>>,
+    last_fetch: Mutex<Option<Instant>>,
+}
+
+impl JwksCache {
+    pub fn new(source: JwksSource) -> Self {
+        Self {
+            source,
+            keys: RwLock::new(HashMap::new()),
+            last_fetch: Mutex::new(None),
+        }
+    }
+
+    pub fn with_keys(keys: HashMap<String, PKey<Public>>) -> Self {
+        Self {
+            source: JwksSource::Static,
+            keys: RwLock::new(keys),
+            last_fetch: Mutex::new(None),
+        }
+    }
+
+    /// Uses `QUOTEFAULT_OIDC_JWKS_URL` if set, otherwise discovers the JWKS
+    /// URL from the issuer's `.well-known/openid-configuration`.
+    pub fn from_env(issuer: Option<&str>) -> Self {
+        Self::new(match env::var("QUOTEFAULT_OIDC_JWKS_URL") {
+            Ok(url) if !url.is_empty() => JwksSource::Url(url),
+            _ => JwksSource::Issuer(issuer.unwrap_or(DEFAULT_ISSUER).to_string()),
+        })
+    }
+
+    pub fn get(&self, kid: &str) -> Option<PKey<Public>> {
+        self.keys
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(kid)
+            .cloned()
+    }
+
+    /// Looks up `kid`, refetching the key set if it is unknown and the last
+    /// fetch is older than `MIN_REFETCH_INTERVAL`.
+    pub async fn get_or_refetch(&self, kid: &str) -> Option<PKey<Public>> {
+        if let Some(key) = self.get(kid) {
+            return Some(key);
+        }
+        self.refresh(false).await;
+        self.get(kid)
+    }
+
+    pub async fn refresh(&self, force: bool) {
+        if let JwksSource::Static = self.source {
+            return;
+        }
+        let mut last_fetch = self.last_fetch.lock().await;
+        if !force && last_fetch.is_some_and(|t| t.elapsed() < MIN_REFETCH_INTERVAL) {
+            return;
+        }
+        *last_fetch = Some(Instant::now());
+
+        let keys = match &self.source {
+            JwksSource::Url(url) => fetch_keys(url).await,
+            JwksSource::Issuer(issuer) => match discover_jwks_url(issuer).await {
+                Ok(url) => fetch_keys(&url).await,
+                Err(err) => Err(err),
+            },
+            JwksSource::Static => return,
+        };
+        match keys {
+            Ok(keys) => {
+                log!(Level::Debug, "Fetched {} signing keys", keys.len());
+                *self.keys.write().unwrap_or_else(|e| e.into_inner()) = keys;
+            }
+            Err(err) => log!(Level::Warn, "Failed to fetch JWKS: {err}"),
+        }
+    }
+
+    pub fn spawn_refresh(&'static self) {
+        if let JwksSource::Static = self.source {
+            return;
+        }
+        actix_web::rt::spawn(async move {
+            loop {
+                self.refresh(true).await;
+                actix_web::rt::time::sleep(REFRESH_INTERVAL).await;
+            }
+        });
+    }
+}
+
+async fn get_json<T: serde::de::DeserializeOwned + Unpin>(url: &str) -> Result<T> {
+    let mut response = Request::get(url)
+        .timeout(FETCH_TIMEOUT)
+        .body(())?
+        .send_async()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("{url} returned {}", response.status()));
+    }
+    Ok(response.json().await?)
+}
+
+async fn discover_jwks_url(issuer: &str) -> Result<String> {
+    let issuer = issuer.trim_end_matches('/');
+    let config: OpenIdConfiguration =
+        get_json(&format!("{issuer}/.well-known/openid-configuration")).await?;
+    if config.issuer.trim_end_matches('/') != issuer {
+        return Err(anyhow!(
+            "discovery document for {issuer} names issuer {}",
+            config.issuer
+        ));
+    }
+    Ok(config.jwks_uri)
+}
+
+async fn fetch_keys(url: &str) -> Result<HashMap<String, PKey<Public>>> {
+    let cert_data: CertData = get_json(url).await?;
+
+    let keys: HashMap<String, PKey<Public>> = cert_data
+        .keys
+        .into_iter()
+        .filter(|key| key.kty == "RSA" && key.r#use.as_deref().unwrap_or("sig") == "sig")
+        .filter_map(|key| match parse_key(&key) {
+            Ok(pkey) => Some((key.kid, pkey)),
+            Err(err) => {
+                log!(Level::Warn, "Skipping signing key {}: {err}", key.kid);
+                None
+            }
+        })
+        .collect();
+    if keys.is_empty() {
+        return Err(anyhow!("{url} has no usable signing keys"));
+    }
+    Ok(keys)
+}
+
+fn parse_key(key: &CertKey) -> Result<PKey<Public>> {
+    let n = general_purpose::URL_SAFE_NO_PAD.decode(key.n.as_deref().ok_or(anyhow!("!n"))?)?;
+    let e = general_purpose::URL_SAFE_NO_PAD.decode(key.e.as_deref().ok_or(anyhow!("!e"))?)?;
+    let rsa = Rsa::from_public_components(BigNum::from_slice(&n)?, BigNum::from_slice(&e)?)?;
+    Ok(PKey::from_rsa(rsa)?)
+}
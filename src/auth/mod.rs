@@ -0,0 +1,378 @@
+use crate::{api::error::ApiError, app::AppState, ldap};
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    web::Data,
+    FromRequest, HttpMessage, HttpRequest, ResponseError,
+};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use futures::future::LocalBoxFuture;
+use lazy_static::lazy_static;
+use log::{log, Level};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::{
+    env,
+    future::{ready, Ready},
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use self::cache::TokenCache;
+use self::dev::DevKey;
+use self::jwks::JwksCache;
+use self::roles::{Role, RoleMapping};
+use self::token::{verify_signature, TokenError, TokenValidation};
+
+pub mod api_token;
+pub mod cache;
+pub mod dev;
+pub mod jwks;
+pub mod roles;
+pub mod token;
+
+lazy_static! {
+    pub static ref DEV_AUTH: bool = env::var("QUOTEFAULT_DEV_AUTH")
+        .map(|x| x.parse::<bool>().unwrap_or(false))
+        .unwrap_or(false);
+    pub static ref DEV_KEY: Option<DevKey> =
+        DEV_AUTH.then(|| DevKey::generate().expect("Failed to generate dev auth keypair"));
+    pub static ref TOKEN_VALIDATION: TokenValidation = TokenValidation::from_env();
+    pub static ref ROLES: RoleMapping = RoleMapping::from_env();
+    static ref TOKEN_CACHE: TokenCache = TokenCache::new();
+    pub static ref JWKS: JwksCache = match &*DEV_KEY {
+        Some(key) => JwksCache::with_keys(key.public_keys()),
+        None => JwksCache::from_env(TOKEN_VALIDATION.issuer.as_deref()),
+    };
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenHeader {
+    alg: String,
+    kid: String,
+    typ: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    exp: u32,
+    iat: u32,
+    nbf: Option<u32>,
+    auth_time: Option<u32>,
+    jti: String,
+    iss: String,
+    #[serde(default, deserialize_with = "token::one_or_many")]
+    aud: Vec<String>,
+    sub: String,
+    typ: String,
+    azp: String,
+    nonce: Option<String>,
+    session_state: Option<String>,
+    scope: String,
+    sid: Option<String>,
+    email_verified: bool,
+    pub name: Option<String>,
+    pub groups: Vec<String>,
+    pub preferred_username: String,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub email: Option<String>,
+    #[serde(skip)]
+    role: Role,
+}
+
+impl FromRequest for User {
+    type Error = actix_web::error::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        // CSHAuth has already authenticated the request, whether or not it
+        // requires a token
+        let result = match req.extensions().get::<Authentication>() {
+            Some(Authentication(Some(user))) => Ok(user.clone()),
+            Some(Authentication(None)) => Err(ApiError::unauthorized().into()),
+            None => {
+                log!(
+                    Level::Error,
+                    "User extracted on {} without CSHAuth middleware",
+                    req.path()
+                );
+                Err(ApiError::internal().into())
+            }
+        };
+        ready(result)
+    }
+}
+
+/// Result of authenticating a request, stored in its extensions by `CSHAuth`
+struct Authentication(Option<User>);
+
+impl User {
+    /// Builds the user of an API token, whose role is capped by its scope
+    fn from_api_token(username: String, groups: Vec<String>, max_role: Role) -> Self {
+        let role = ROLES.resolve_ldap(&groups).min(max_role);
+        Self {
+            exp: 0,
+            iat: 0,
+            nbf: None,
+            auth_time: None,
+            jti: String::new(),
+            iss: String::new(),
+            aud: Vec::new(),
+            sub: username.clone(),
+            typ: api_token::TOKEN_TYPE.to_string(),
+            azp: String::new(),
+            nonce: None,
+            session_state: None,
+            scope: String::new(),
+            sid: None,
+            email_verified: false,
+            name: None,
+            groups,
+            preferred_username: username,
+            given_name: None,
+            family_name: None,
+            email: None,
+            role,
+        }
+    }
+
+    /// Whether the user authenticated with a personal API token instead of SSO
+    pub fn is_api_token(&self) -> bool {
+        self.typ == api_token::TOKEN_TYPE
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn has_role(&self, role: Role) -> bool {
+        self.role >= role
+    }
+}
+
+fn app_state(req: &HttpRequest) -> Option<Data<AppState>> {
+    req.app_data::<Data<AppState>>().cloned()
+}
+
+pub(crate) fn hash_token(token: &str) -> Vec<u8> {
+    Sha3_256::digest(token.as_bytes()).to_vec()
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.trim_start_matches("Bearer ").to_string())
+}
+
+/// Verifies a JWT or looks up a personal API token, then resolves the user's
+/// role. API tokens need `AppState` for the database.
+async fn authenticate(token: String, state: Option<Data<AppState>>) -> Option<User> {
+    let ldap = state.as_ref().map(|state| &state.ldap);
+    if api_token::is_api_token(&token) {
+        let state = state.as_ref()?;
+        let (owner, max_role) = match api_token::lookup(&state.db, &token).await {
+            Ok(found) => found?,
+            Err(err) => {
+                log!(Level::Error, "Failed to look up API token: {err}");
+                return None;
+            }
+        };
+        // The owner's groups may have changed since the token was created
+        let groups = match ldap::get_user(&state.ldap, &owner).await {
+            Ok(users) => users.into_iter().next()?.groups,
+            Err(err) => {
+                log!(Level::Error, "Failed to look up groups of {owner}: {err}");
+                return None;
+            }
+        };
+        return Some(User::from_api_token(owner, groups, max_role));
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let hash = hash_token(&token);
+    if let Some(user) = TOKEN_CACHE.get(&hash, now) {
+        return Some(user);
+    }
+
+    let (header, header_64, mut user, user_64, signature) = match get_token_pieces(token) {
+        Ok(x) => x,
+        Err(e) => {
+            log!(Level::Debug, "Token is formated incorrectly: {e}");
+            return None;
+        }
+    };
+    if !verify_token(&header, &header_64, &user, &user_64, &signature).await {
+        return None;
+    }
+    user.role = ROLES
+        .resolve(&user.preferred_username, &user.groups, ldap)
+        .await;
+    TOKEN_CACHE.insert(hash, user.clone(), now);
+    Some(user)
+}
+
+#[doc(hidden)]
+pub struct CSHAuthService<S> {
+    service: Rc<S>,
+    enabled: bool,
+    role: Role,
+}
+
+fn get_token_pieces(token: String) -> Result<(TokenHeader, String, User, String, Vec<u8>)> {
+    let mut it = token.split('.');
+    let token_header_base64 = it.next().ok_or(anyhow!("!header"))?;
+    let token_header = general_purpose::URL_SAFE_NO_PAD.decode(token_header_base64)?;
+    let token_header: TokenHeader = serde_json::from_slice(&token_header)?;
+    let token_payload_base64 = it.next().ok_or(anyhow!("!body"))?;
+    let token_payload = general_purpose::URL_SAFE_NO_PAD.decode(token_payload_base64)?;
+    let token_payload: User = serde_json::from_slice(&token_payload)?;
+    let token_signature = it.next().ok_or(anyhow!("signature"))?;
+    let token_signature = general_purpose::URL_SAFE_NO_PAD.decode(token_signature)?;
+    Ok((
+        token_header,
+        token_header_base64.to_owned(),
+        token_payload,
+        token_payload_base64.to_owned(),
+        token_signature,
+    ))
+}
+
+async fn verify_token(
+    header: &TokenHeader,
+    header_64: &str,
+    payload: &User,
+    payload_64: &str,
+    signature: &[u8],
+) -> bool {
+    let result = match TOKEN_VALIDATION.validate(header, payload, chrono::Utc::now().timestamp()) {
+        Ok(()) => match JWKS.get_or_refetch(header.kid.as_str()).await {
+            Some(pkey) => verify_signature(header_64, payload_64, signature, &pkey),
+            None => Err(TokenError::UnknownKey(header.kid.clone())),
+        },
+        Err(err) => Err(err),
+    };
+    match result {
+        Ok(()) => true,
+        Err(err) => {
+            log!(
+                Level::Debug,
+                "Rejected token for {}: {err}",
+                payload.preferred_username
+            );
+            false
+        }
+    }
+}
+
+impl<S> Service<ServiceRequest> for CSHAuthService<S>
+where
+    S: Service<
+        ServiceRequest,
+        Response = ServiceResponse<actix_web::body::BoxBody>,
+        Error = actix_web::Error,
+    >,
+    S: 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<actix_web::body::BoxBody>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let enabled = self.enabled;
+        let role = self.role;
+        let token = bearer_token(req.request());
+        let state = app_state(req.request());
+
+        Box::pin(async move {
+            let user = match token {
+                Some(token) => authenticate(token, state).await,
+                None => None,
+            };
+            if enabled {
+                match &user {
+                    None => return Ok(req.into_response(ApiError::unauthorized().error_response())),
+                    Some(user) if !user.has_role(role) => {
+                        return Ok(req.into_response(ApiError::forbidden().error_response()))
+                    }
+                    Some(_) => {}
+                }
+            }
+            req.extensions_mut().insert(Authentication(user));
+
+            service.call(req).await
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CSHAuth {
+    enabled: bool,
+    role: Role,
+}
+
+lazy_static! {
+    pub static ref SECURITY_ENABLED: bool = *DEV_AUTH
+        || env::var("SECURITY_ENABLED")
+            .map(|x| x.parse::<bool>().unwrap_or(true))
+            .unwrap_or(true);
+}
+
+impl CSHAuth {
+    pub fn require_role(role: Role) -> Self {
+        Self {
+            enabled: *SECURITY_ENABLED,
+            role,
+        }
+    }
+
+    pub fn admin_only() -> Self {
+        Self::require_role(Role::Admin)
+    }
+
+    pub fn enabled() -> Self {
+        Self::require_role(Role::Viewer)
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            role: Role::Viewer,
+        }
+    }
+}
+
+impl<S> Transform<S, ServiceRequest> for CSHAuth
+where
+    S: Service<
+        ServiceRequest,
+        Response = ServiceResponse<actix_web::body::BoxBody>,
+        Error = actix_web::Error,
+    >,
+    S: 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<actix_web::body::BoxBody>;
+    type Error = actix_web::Error;
+    type Transform = CSHAuthService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CSHAuthService {
+            service: Rc::new(service),
+            enabled: self.enabled,
+            role: self.role,
+        }))
+    }
+}
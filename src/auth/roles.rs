@@ -0,0 +1,202 @@
+use std::{cmp::Reverse, env};
+
+use log::{log, Level};
+use serde::{Deserialize, Serialize};
+
+use crate::ldap::{self, client::LdapClient};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Viewer,
+    Member,
+    Moderator,
+    Admin,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GroupSource {
+    Any,
+    Token(String),
+    Ldap(String),
+}
+
+/// Maps OIDC `groups` claims and LDAP groups to roles. A user gets the
+/// highest role any of their groups maps to, and `Viewer` otherwise.
+#[derive(Debug, Clone)]
+pub struct RoleMapping {
+    rules: Vec<(Role, GroupSource)>,
+}
+
+impl RoleMapping {
+    /// Reads comma separated groups from `QUOTEFAULT_ROLE_ADMIN`,
+    /// `QUOTEFAULT_ROLE_MODERATOR` and `QUOTEFAULT_ROLE_MEMBER`. A group is a
+    /// token `groups` claim, `ldap:<group>` for an LDAP group, or `*` for
+    /// every authenticated user.
+    pub fn from_env() -> Self {
+        Self::parse([
+            (
+                Role::Admin,
+                env::var("QUOTEFAULT_ROLE_ADMIN").unwrap_or("eboard,rtp".to_string()),
+            ),
+            (
+                Role::Moderator,
+                env::var("QUOTEFAULT_ROLE_MODERATOR").unwrap_or_default(),
+            ),
+            (
+                Role::Member,
+                env::var("QUOTEFAULT_ROLE_MEMBER").unwrap_or("*".to_string()),
+            ),
+        ])
+    }
+
+    pub fn parse(config: impl IntoIterator<Item = (Role, String)>) -> Self {
+        let mut rules: Vec<(Role, GroupSource)> = config
+            .into_iter()
+            .flat_map(|(role, groups)| {
+                groups
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|group| !group.is_empty())
+                    .map(|group| {
+                        let source = if group == "*" {
+                            GroupSource::Any
+                        } else if let Some(group) = group.strip_prefix("ldap:") {
+                            GroupSource::Ldap(group.to_string())
+                        } else {
+                            GroupSource::Token(group.to_string())
+                        };
+                        (role, source)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        rules.sort_by_key(|(role, _)| Reverse(*role));
+        Self { rules }
+    }
+
+    fn token_role(&self, groups: &[String]) -> Role {
+        self.rules
+            .iter()
+            .find(|(_, source)| match source {
+                GroupSource::Any => true,
+                GroupSource::Token(group) => groups.contains(group),
+                GroupSource::Ldap(_) => false,
+            })
+            .map(|(role, _)| *role)
+            .unwrap_or_default()
+    }
+
+    /// Role of a user whose groups came from LDAP, so they match both token
+    /// and `ldap:` groups
+    pub fn resolve_ldap(&self, groups: &[String]) -> Role {
+        self.rules
+            .iter()
+            .find(|(_, source)| match source {
+                GroupSource::Any => true,
+                GroupSource::Token(group) | GroupSource::Ldap(group) => groups.contains(group),
+            })
+            .map(|(role, _)| *role)
+            .unwrap_or_default()
+    }
+
+    /// Only queries LDAP when an LDAP group could grant a higher role than
+    /// the token's groups already do.
+    pub async fn resolve(&self, uid: &str, groups: &[String], ldap: Option<&LdapClient>) -> Role {
+        let role = self.token_role(groups);
+        if !self
+            .rules
+            .iter()
+            .any(|(rule_role, source)| matches!(source, GroupSource::Ldap(_)) && *rule_role > role)
+        {
+            return role;
+        }
+        let Some(ldap) = ldap else {
+            log!(Level::Warn, "LDAP role mappings configured without LDAP");
+            return role;
+        };
+
+        let member_of = match ldap::get_user(ldap, uid).await {
+            Ok(users) => users
+                .into_iter()
+                .next()
+                .map(|user| user.groups)
+                .unwrap_or_default(),
+            Err(err) => {
+                log!(
+                    Level::Warn,
+                    "Failed to look up LDAP groups for {uid}: {err}"
+                );
+                return role;
+            }
+        };
+        self.rules
+            .iter()
+            .find(|(_, source)| match source {
+                GroupSource::Ldap(group) => member_of.contains(group),
+                _ => false,
+            })
+            .map_or(role, |(ldap_role, _)| role.max(*ldap_role))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(groups: &[&str]) -> Vec<String> {
+        groups.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[actix_web::test]
+    async fn highest_matching_role_wins() {
+        let mapping = RoleMapping::parse([
+            (Role::Member, "*".to_string()),
+            (Role::Moderator, "rtp".to_string()),
+            (Role::Admin, "eboard, ldap:root".to_string()),
+        ]);
+        assert_eq!(mapping.resolve("a", &groups(&[]), None).await, Role::Member);
+        assert_eq!(
+            mapping.resolve("a", &groups(&["rtp"]), None).await,
+            Role::Moderator
+        );
+        assert_eq!(
+            mapping
+                .resolve("a", &groups(&["rtp", "eboard"]), None)
+                .await,
+            Role::Admin
+        );
+    }
+
+    #[test]
+    fn ldap_groups_match_every_rule() {
+        let mapping = RoleMapping::parse([
+            (Role::Member, "*".to_string()),
+            (Role::Moderator, "ldap:rtp".to_string()),
+            (Role::Admin, "eboard".to_string()),
+        ]);
+        assert_eq!(mapping.resolve_ldap(&groups(&[])), Role::Member);
+        assert_eq!(mapping.resolve_ldap(&groups(&["rtp"])), Role::Moderator);
+        assert_eq!(
+            mapping.resolve_ldap(&groups(&["rtp", "eboard"])),
+            Role::Admin
+        );
+    }
+
+    #[actix_web::test]
+    async fn unmapped_users_are_viewers() {
+        let mapping = RoleMapping::parse([
+            (Role::Member, "member".to_string()),
+            (Role::Admin, String::new()),
+        ]);
+        assert_eq!(
+            mapping.resolve("a", &groups(&["alumni"]), None).await,
+            Role::Viewer
+        );
+        assert_eq!(
+            mapping.resolve("a", &groups(&["member"]), None).await,
+            Role::Member
+        );
+    }
+}
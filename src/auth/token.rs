@@ -0,0 +1,355 @@
+use std::{
+    env,
+    fmt::{self, Display},
+    iter,
+};
+
+use openssl::{
+    hash::MessageDigest,
+    pkey::{PKey, Public},
+    sign::Verifier,
+};
+use serde::{Deserialize, Deserializer};
+
+use super::{TokenHeader, User};
+
+pub const DEFAULT_ISSUER: &str = "https://sso.csh.rit.edu/auth/realms/csh";
+pub const DEFAULT_AUDIENCE: &str = "quotefault";
+/// `typ` of access tokens, as opposed to `ID` and `Refresh` tokens
+const ACCESS_TOKEN_TYPE: &str = "Bearer";
+const DEFAULT_LEEWAY: i64 = 60;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenError {
+    UnsupportedAlgorithm(String),
+    WrongType(String),
+    Expired,
+    NotYetValid,
+    IssuedInFuture,
+    WrongIssuer(String),
+    WrongAudience(Vec<String>),
+    UnknownKey(String),
+    BadSignature,
+}
+
+impl Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::UnsupportedAlgorithm(alg) => write!(f, "unsupported algorithm {alg}"),
+            Self::WrongType(typ) => write!(f, "not an access token ({typ})"),
+            Self::Expired => write!(f, "token expired"),
+            Self::NotYetValid => write!(f, "token not valid yet (nbf)"),
+            Self::IssuedInFuture => write!(f, "token issued in the future (iat)"),
+            Self::WrongIssuer(iss) => write!(f, "unexpected issuer {iss}"),
+            Self::WrongAudience(aud) => write!(f, "unexpected audience {}", aud.join(",")),
+            Self::UnknownKey(kid) => write!(f, "unknown signing key {kid}"),
+            Self::BadSignature => write!(f, "bad signature"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenValidation {
+    /// Expected `iss`, or `None` to accept any issuer
+    pub issuer: Option<String>,
+    /// Accepted `aud`/`azp` values, or empty to accept any audience. Never
+    /// empty when read from the environment
+    pub audiences: Vec<String>,
+    /// Allowed clock skew in seconds for `exp`, `nbf` and `iat`
+    pub leeway: i64,
+}
+
+impl TokenValidation {
+    pub fn from_env() -> Self {
+        Self {
+            issuer: match env::var("QUOTEFAULT_OIDC_ISSUER") {
+                Ok(x) if x.is_empty() => None,
+                Ok(x) => Some(x.trim_end_matches('/').to_string()),
+                Err(_) => Some(DEFAULT_ISSUER.to_string()),
+            },
+            audiences: env::var("QUOTEFAULT_OIDC_AUDIENCE")
+                .map(|x| {
+                    x.split(',')
+                        .map(str::trim)
+                        .filter(|x| !x.is_empty())
+                        .map(String::from)
+                        .collect::<Vec<_>>()
+                })
+                .ok()
+                .filter(|x| !x.is_empty())
+                .unwrap_or(vec![DEFAULT_AUDIENCE.to_string()]),
+            leeway: env::var("QUOTEFAULT_OIDC_LEEWAY")
+                .ok()
+                .and_then(|x| x.parse().ok())
+                .unwrap_or(DEFAULT_LEEWAY),
+        }
+    }
+
+    pub fn validate(&self, header: &TokenHeader, user: &User, now: i64) -> Result<(), TokenError> {
+        if header.alg != "RS256" {
+            return Err(TokenError::UnsupportedAlgorithm(header.alg.clone()));
+        }
+        if user.typ != ACCESS_TOKEN_TYPE {
+            return Err(TokenError::WrongType(user.typ.clone()));
+        }
+        if i64::from(user.exp) + self.leeway < now {
+            return Err(TokenError::Expired);
+        }
+        if user
+            .nbf
+            .is_some_and(|nbf| i64::from(nbf) - self.leeway > now)
+        {
+            return Err(TokenError::NotYetValid);
+        }
+        if i64::from(user.iat) - self.leeway > now {
+            return Err(TokenError::IssuedInFuture);
+        }
+        if let Some(issuer) = &self.issuer {
+            if user.iss.trim_end_matches('/') != issuer {
+                return Err(TokenError::WrongIssuer(user.iss.clone()));
+            }
+        }
+        if !self.audiences.is_empty()
+            && !user
+                .aud
+                .iter()
+                .chain(iter::once(&user.azp))
+                .any(|aud| self.audiences.contains(aud))
+        {
+            return Err(TokenError::WrongAudience(user.aud.clone()));
+        }
+        Ok(())
+    }
+}
+
+pub fn verify_signature(
+    header_64: &str,
+    payload_64: &str,
+    signature: &[u8],
+    pkey: &PKey<Public>,
+) -> Result<(), TokenError> {
+    let verified = Verifier::new(MessageDigest::sha256(), pkey)
+        .and_then(|mut verifier| {
+            verifier.update(header_64.as_bytes())?;
+            verifier.update(b".")?;
+            verifier.update(payload_64.as_bytes())?;
+            verifier.verify(signature)
+        })
+        .unwrap_or(false);
+    if verified {
+        Ok(())
+    } else {
+        Err(TokenError::BadSignature)
+    }
+}
+
+/// `aud` may be a single string or an array of strings
+pub(super) fn one_or_many<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(aud) => vec![aud],
+        OneOrMany::Many(aud) => aud,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose, Engine as _};
+    use openssl::{
+        pkey::{PKey, Private},
+        rsa::Rsa,
+        sign::Signer,
+    };
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::auth::get_token_pieces;
+
+    const NOW: i64 = 1_700_000_000;
+    const ISSUER: &str = "https://sso.example.com/realms/test";
+
+    fn keypair() -> (PKey<Private>, PKey<Public>) {
+        let rsa = Rsa::generate(2048).unwrap();
+        let public = PKey::from_rsa(
+            Rsa::from_public_components(rsa.n().to_owned().unwrap(), rsa.e().to_owned().unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+        (PKey::from_rsa(rsa).unwrap(), public)
+    }
+
+    fn claims() -> Value {
+        json!({
+            "exp": NOW + 300,
+            "iat": NOW - 10,
+            "jti": "jti",
+            "iss": ISSUER,
+            "aud": "quotefault",
+            "sub": "sub",
+            "typ": "Bearer",
+            "azp": "quotefault",
+            "scope": "openid",
+            "email_verified": true,
+            "groups": ["member"],
+            "preferred_username": "cole",
+        })
+    }
+
+    fn sign(claims: &Value, key: &PKey<Private>) -> String {
+        let header = general_purpose::URL_SAFE_NO_PAD
+            .encode(json!({"alg": "RS256", "kid": "test", "typ": "JWT"}).to_string());
+        let payload = general_purpose::URL_SAFE_NO_PAD.encode(claims.to_string());
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer
+            .update(format!("{header}.{payload}").as_bytes())
+            .unwrap();
+        let signature = general_purpose::URL_SAFE_NO_PAD.encode(signer.sign_to_vec().unwrap());
+        format!("{header}.{payload}.{signature}")
+    }
+
+    fn validation() -> TokenValidation {
+        TokenValidation {
+            issuer: Some(ISSUER.to_string()),
+            audiences: vec!["quotefault".to_string()],
+            leeway: 30,
+        }
+    }
+
+    fn check(claims: &Value, validation: &TokenValidation) -> Result<(), TokenError> {
+        let (private, public) = keypair();
+        let (header, header_64, user, user_64, signature) =
+            get_token_pieces(sign(claims, &private)).unwrap();
+        verify_signature(&header_64, &user_64, &signature, &public)?;
+        validation.validate(&header, &user, NOW)
+    }
+
+    #[test]
+    fn accepts_valid_token() {
+        assert_eq!(check(&claims(), &validation()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_signature_from_other_key() {
+        let (private, _) = keypair();
+        let (_, public) = keypair();
+        let (_, header_64, _, user_64, signature) =
+            get_token_pieces(sign(&claims(), &private)).unwrap();
+        assert_eq!(
+            verify_signature(&header_64, &user_64, &signature, &public),
+            Err(TokenError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_payload() {
+        let (private, public) = keypair();
+        let (_, header_64, _, _, signature) = get_token_pieces(sign(&claims(), &private)).unwrap();
+        let mut tampered = claims();
+        tampered["groups"] = json!(["eboard"]);
+        let tampered_64 = general_purpose::URL_SAFE_NO_PAD.encode(tampered.to_string());
+        assert_eq!(
+            verify_signature(&header_64, &tampered_64, &signature, &public),
+            Err(TokenError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn rejects_expired_token_outside_leeway() {
+        let mut claims = claims();
+        claims["exp"] = json!(NOW - 20);
+        assert_eq!(check(&claims, &validation()), Ok(()));
+        claims["exp"] = json!(NOW - 31);
+        assert_eq!(check(&claims, &validation()), Err(TokenError::Expired));
+    }
+
+    #[test]
+    fn rejects_token_before_nbf() {
+        let mut claims = claims();
+        claims["nbf"] = json!(NOW + 20);
+        assert_eq!(check(&claims, &validation()), Ok(()));
+        claims["nbf"] = json!(NOW + 31);
+        assert_eq!(check(&claims, &validation()), Err(TokenError::NotYetValid));
+    }
+
+    #[test]
+    fn rejects_token_issued_in_future() {
+        let mut claims = claims();
+        claims["iat"] = json!(NOW + 31);
+        assert_eq!(
+            check(&claims, &validation()),
+            Err(TokenError::IssuedInFuture)
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_issuer() {
+        let mut claims = claims();
+        claims["iss"] = json!("https://sso.example.com/realms/other");
+        assert_eq!(
+            check(&claims, &validation()),
+            Err(TokenError::WrongIssuer(
+                "https://sso.example.com/realms/other".to_string()
+            ))
+        );
+        let validation = TokenValidation {
+            issuer: None,
+            ..validation()
+        };
+        assert_eq!(check(&claims, &validation), Ok(()));
+    }
+
+    #[test]
+    fn checks_audience_and_azp() {
+        let mut claims = claims();
+        claims["aud"] = json!(["account", "quotefault"]);
+        claims["azp"] = json!("other");
+        assert_eq!(check(&claims, &validation()), Ok(()));
+
+        claims["aud"] = json!("account");
+        claims["azp"] = json!("quotefault");
+        assert_eq!(check(&claims, &validation()), Ok(()));
+
+        claims["azp"] = json!("other");
+        assert_eq!(
+            check(&claims, &validation()),
+            Err(TokenError::WrongAudience(vec!["account".to_string()]))
+        );
+
+        let validation = TokenValidation {
+            audiences: vec![],
+            ..validation()
+        };
+        assert_eq!(check(&claims, &validation), Ok(()));
+    }
+
+    #[test]
+    fn rejects_id_and_refresh_tokens() {
+        let mut claims = claims();
+        for typ in ["ID", "Refresh", "Offline"] {
+            claims["typ"] = json!(typ);
+            assert_eq!(
+                check(&claims, &validation()),
+                Err(TokenError::WrongType(typ.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_other_algorithms() {
+        let (private, _) = keypair();
+        let (mut header, _, user, _, _) = get_token_pieces(sign(&claims(), &private)).unwrap();
+        header.alg = "HS256".to_string();
+        assert_eq!(
+            validation().validate(&header, &user, NOW),
+            Err(TokenError::UnsupportedAlgorithm("HS256".to_string()))
+        );
+    }
+}
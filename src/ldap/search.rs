@@ -31,6 +31,7 @@ impl SearchAttrs {
     }
 
     #[must_use]
+    #[allow(clippy::should_implement_trait)]
     pub fn add(mut self, attr: &str) -> Self {
         if !(self.attrs.contains(&attr.to_string())) {
             self.attrs.push(attr.to_string());
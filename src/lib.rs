@@ -10,7 +10,14 @@ pub mod schema {
 }
 
 pub mod api {
+    pub mod cursor;
     pub mod db;
+    pub mod dev;
     pub mod endpoints;
+    pub mod error;
+    pub mod openapi;
+    pub mod params;
     pub mod pings;
+    pub mod settings;
+    pub mod tokens;
 }
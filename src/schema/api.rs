@@ -1,93 +1,349 @@
-use crate::schema::db::Vote;
+use crate::schema::db::{ApiTokenScope, HideAction, ModerationAction, Vote};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct NewQuote {
     pub shards: Vec<NewQuoteShard>,
+    /// Replaces the quote's tags when editing if present
+    pub tags: Option<Vec<String>>,
+    /// When the quote was said, if not when it was submitted. Cleared when
+    /// editing if absent
+    pub said_at: Option<chrono::NaiveDateTime>,
+    /// Where or during what the quote was said. Cleared when editing if
+    /// absent or blank
+    pub context: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct Tags {
+    pub tags: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct NewQuoteShard {
     pub body: String,
     pub speaker: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct Reason {
     pub reason: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FetchParams {
+    /// Full-text search over shard bodies
     pub q: Option<String>,
+    /// Only quotes with an id less than this
     pub lt: Option<i32>,
+    /// Maximum number of quotes, or -1 for all (default: 10)
     pub limit: Option<i64>,
-    pub submitter: Option<String>,
-    pub speaker: Option<String>,
-    pub involved: Option<String>,
+    /// Only quotes submitted by one of these users. Repeatable
+    #[serde(default)]
+    pub submitter: Vec<String>,
+    /// Only quotes said by these users. Repeatable
+    #[serde(default)]
+    pub speaker: Vec<String>,
+    /// Only quotes submitted or said by these users. Repeatable
+    #[serde(default)]
+    pub involved: Vec<String>,
+    /// Whether `speaker` and `involved` match quotes with `any` or `all` of
+    /// the users (default: `any`)
+    #[serde(rename = "match")]
+    pub match_mode: Option<MatchMode>,
+    /// Only hidden quotes if true, only visible quotes if false
     pub hidden: Option<bool>,
+    /// Only favorited quotes if true
     pub favorited: Option<bool>,
+    /// Only quotes awaiting your approval if true
+    pub pending: Option<bool>,
+    /// Only quotes with this tag
+    pub tag: Option<String>,
+    /// Only quotes said at or after this time
+    pub said_after: Option<chrono::NaiveDateTime>,
+    /// Only quotes said before this time
+    pub said_before: Option<chrono::NaiveDateTime>,
+    /// Only quotes submitted at or after this time
+    pub after: Option<chrono::NaiveDateTime>,
+    /// Only quotes submitted before this time
+    pub before: Option<chrono::NaiveDateTime>,
+    /// `date`, `said_at`, `votes`, `hot`, `controversial` or `relevance`
+    /// (default: `date`)
     pub sort: Option<String>,
+    /// Ascending if true (default: false)
     pub sort_direction: Option<bool>,
+    /// `next_cursor` of a previous response with the same sort and filters
+    pub cursor: Option<String>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    Any,
+    All,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
 pub struct Hidden {
     pub reason: String,
     pub actor: UserResponse,
+    pub timestamp: chrono::NaiveDateTime,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
+pub struct HideEventResponse {
+    pub action: HideAction,
+    pub reason: Option<String>,
+    pub actor: UserResponse,
+    pub timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
 pub struct QuoteResponse {
     pub submitter: UserResponse,
     pub timestamp: chrono::NaiveDateTime,
+    pub edited: Option<chrono::NaiveDateTime>,
+    pub said_at: Option<chrono::NaiveDateTime>,
+    pub context: Option<String>,
     pub shards: Vec<QuoteShardResponse>,
     pub id: i32,
     pub vote: Option<Vote>,
     pub score: i64,
+    pub upvotes: i64,
+    pub downvotes: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relevance: Option<f32>,
     pub hidden: Option<Hidden>,
     pub favorited: bool,
+    /// Awaiting approval from a speaker, and only visible to the submitter and speakers
+    pub pending: bool,
+    pub tags: Vec<String>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
+pub struct QuotesResponse {
+    pub quotes: Vec<QuoteResponse>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
 pub struct QuoteShardResponse {
     pub body: String,
     pub speaker: UserResponse,
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
+pub struct QuoteRevisionResponse {
+    pub revision: i32,
+    pub editor: UserResponse,
+    pub timestamp: chrono::NaiveDateTime,
+    pub shards: Vec<QuoteShardResponse>,
+}
+
+#[derive(Serialize, Clone, Debug, ToSchema)]
 pub struct UserResponse {
     pub cn: String,
     pub uid: String,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum LeaderboardWindow {
+    Week,
+    Month,
+    Semester,
+    AllTime,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeaderboardParams {
+    /// Only quotes submitted in the last 7 days, the last 30 days, this
+    /// semester or at any time (default: `all-time`). Favorites aren't
+    /// timestamped, so the most favorited quotes are those submitted in the
+    /// window, with all of their favorites counted.
+    pub window: Option<LeaderboardWindow>,
+    /// Maximum number of entries on each board, up to 50 (default: 10)
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct UserCountResponse {
+    pub user: UserResponse,
+    pub count: i64,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct FavoritedQuoteResponse {
+    pub quote: QuoteResponse,
+    pub favorites: i64,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct LeaderboardResponse {
+    pub window: LeaderboardWindow,
+    /// Start of the window, or null for all time
+    pub since: Option<chrono::NaiveDateTime>,
+    /// Users quoted in the most quotes
+    pub speakers: Vec<UserCountResponse>,
+    /// Users who submitted the most quotes
+    pub submitters: Vec<UserCountResponse>,
+    /// Quotes with the highest score
+    pub top_quotes: Vec<QuoteResponse>,
+    /// Quotes favorited by the most users
+    pub most_favorited: Vec<FavoritedQuoteResponse>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct UserStatsResponse {
+    pub user: UserResponse,
+    /// Visible quotes the user is quoted in
+    pub quotes_said: i64,
+    /// Visible quotes the user submitted
+    pub quotes_submitted: i64,
+    pub votes_cast: i64,
+    /// Total score of the visible quotes the user is quoted in
+    pub score_received: i64,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
 pub struct ReportedQuoteResponse {
     pub quote_id: i32,
     pub reports: Vec<ReportResponse>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct ReportResponse {
     pub reason: String,
     pub timestamp: chrono::NaiveDateTime,
     pub id: i32,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ResolveParams {
+    /// Also hide the quote (default: false)
     pub hide: Option<bool>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ModerationLogParams {
+    pub actor: Option<String>,
+    pub quote_id: Option<i32>,
+    pub action: Option<ModerationAction>,
+    pub after: Option<chrono::NaiveDateTime>,
+    pub before: Option<chrono::NaiveDateTime>,
+    pub lt: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct VoteParams {
     pub vote: Vote,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct VersionResponse {
     pub revision: String,
     pub date: String,
     pub build_date: String,
     pub url: String,
 }
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    Unauthorized,
+    Forbidden,
+    InternalError,
+    LdapError,
+    NoShards,
+    TooManyShards,
+    InvalidUsername,
+    SelfQuote,
+    UnknownUsers,
+    QuoteNotFound,
+    NotSubmitter,
+    HideNotAllowed,
+    UnhideNotAllowed,
+    ReasonTooShort,
+    AlreadyReported,
+    NoOpenReports,
+    AlreadyFavorited,
+    NotFavorited,
+    InvalidCursor,
+    CursorMismatch,
+    InvalidTokenName,
+    ScopeNotAllowed,
+    TokenNotFound,
+    NoPendingApproval,
+    OptedOut,
+    InvalidTag,
+    TagEditNotAllowed,
+    InvalidContext,
+    InvalidExpiry,
+    UserNotFound,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DevTokenRequest {
+    pub username: String,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    pub name: Option<String>,
+    pub ttl: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DevTokenResponse {
+    pub token: String,
+    pub expires: i64,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct NewApiToken {
+    pub name: String,
+    pub scope: ApiTokenScope,
+    /// In the future and at most 365 days away
+    pub expires: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct NewApiTokenResponse {
+    pub id: i32,
+    /// Only returned once, at creation
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct UserSettings {
+    /// New quotes of you stay pending until you approve them
+    pub require_approval: bool,
+    /// You can't be quoted
+    pub opt_out: bool,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct OptOut {
+    pub opt_out: bool,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OptOutParams {
+    /// When opting out, also hide every existing quote of the user (default: false)
+    pub hide_existing: Option<bool>,
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 pub struct ID {
     pub id: i32, // SERIAL value
 }
@@ -11,11 +12,78 @@ pub struct QuoteShard {
     pub submitter: String,
     pub speaker: String,
     pub timestamp: chrono::NaiveDateTime,
+    pub edited: Option<chrono::NaiveDateTime>,
+    pub said_at: Option<chrono::NaiveDateTime>,
+    pub context: Option<String>,
     pub vote: Option<Vote>,
     pub score: i64,
+    pub upvotes: i64,
+    pub downvotes: i64,
+    pub relevance: Option<f32>,
+    pub sort_key: Option<String>,
     pub hidden_reason: Option<String>,
     pub hidden_actor: Option<String>,
+    pub hidden_timestamp: Option<chrono::NaiveDateTime>,
     pub favorited: bool,
+    pub pending: bool,
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RevisionShard {
+    pub revision: i32,
+    pub index: i16,
+    pub body: String,
+    pub speaker: String,
+    pub editor: String,
+    pub timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize, Debug)]
+pub struct HideEvent {
+    pub action: HideAction,
+    pub reason: Option<String>,
+    pub actor: String,
+    pub timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ModerationLogEntry {
+    pub id: i32,
+    pub actor: String,
+    pub action: ModerationAction,
+    pub quote_id: Option<i32>,
+    pub details: Option<String>,
+    pub timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct QuoteVote {
+    pub voter: String,
+    pub vote: Vote,
+    pub timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ApiToken {
+    pub id: i32,
+    pub name: String,
+    pub scope: ApiTokenScope,
+    pub created: chrono::NaiveDateTime,
+    pub expires: chrono::NaiveDateTime,
+    pub last_used: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UserCount {
+    pub username: String,
+    pub count: i64,
 }
 
 #[derive(Serialize, Debug)]
@@ -30,10 +98,40 @@ pub struct ReportedQuoteShard {
     pub report_resolver: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, PartialOrd, sqlx::Type, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, PartialOrd, sqlx::Type, Serialize, Deserialize, ToSchema)]
 #[sqlx(type_name = "vote", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum Vote {
     Upvote,
     Downvote,
 }
+
+#[derive(Clone, Debug, PartialEq, sqlx::Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "hide_action", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum HideAction {
+    Hide,
+    Unhide,
+}
+
+#[derive(Clone, Debug, PartialEq, sqlx::Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "moderation_action", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    Hide,
+    Unhide,
+    Resolve,
+    Edit,
+    Delete,
+    ViewHidden,
+    OptOut,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, sqlx::Type, Serialize, Deserialize, ToSchema)]
+#[sqlx(type_name = "api_token_scope", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ApiTokenScope {
+    Read,
+    Write,
+    Moderate,
+}
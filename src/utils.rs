@@ -1,3 +1,11 @@
 pub fn is_valid_username(username: &str) -> bool {
     username.len() <= 32 && username.chars().any(|x| x.is_ascii_alphanumeric())
 }
+
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= 32
+        && tag
+            .chars()
+            .all(|x| x.is_ascii_lowercase() || x.is_ascii_digit() || x == '-')
+}
@@ -0,0 +1,186 @@
+use std::{env, net::TcpListener};
+
+use actix_web::{
+    get,
+    http::StatusCode,
+    test,
+    web::{self, Data},
+    App, HttpResponse, HttpServer, Responder,
+};
+use base64::{engine::general_purpose, Engine as _};
+use openssl::{
+    hash::MessageDigest,
+    pkey::{PKey, Private},
+    rsa::Rsa,
+    sign::Signer,
+};
+use quotefault_backend::auth::{CSHAuth, User};
+use serde_json::{json, Value};
+
+struct Idp {
+    issuer: String,
+    key: PKey<Private>,
+}
+
+#[get("/realms/test/.well-known/openid-configuration")]
+async fn openid_configuration(idp: Data<Idp>) -> impl Responder {
+    HttpResponse::Ok().json(json!({
+        "issuer": idp.issuer,
+        "jwks_uri": format!("{}/protocol/openid-connect/certs", idp.issuer),
+    }))
+}
+
+#[get("/realms/test/protocol/openid-connect/certs")]
+async fn certs(idp: Data<Idp>) -> impl Responder {
+    let rsa = idp.key.rsa().unwrap();
+    HttpResponse::Ok().json(json!({
+        "keys": [{
+            "kid": "test",
+            "kty": "RSA",
+            "alg": "RS256",
+            "use": "sig",
+            "n": general_purpose::URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+            "e": general_purpose::URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+        }]
+    }))
+}
+
+#[get("/whoami", wrap = "CSHAuth::enabled()")]
+async fn whoami(user: User) -> impl Responder {
+    HttpResponse::Ok().body(user.preferred_username)
+}
+
+#[get("/admin", wrap = "CSHAuth::admin_only()")]
+async fn admin(user: User) -> impl Responder {
+    HttpResponse::Ok().body(user.preferred_username)
+}
+
+#[get("/unguarded")]
+async fn unguarded(user: User) -> impl Responder {
+    HttpResponse::Ok().body(user.preferred_username)
+}
+
+fn sign(kid: &str, claims: &Value, key: &PKey<Private>) -> String {
+    let header = general_purpose::URL_SAFE_NO_PAD
+        .encode(json!({"alg": "RS256", "kid": kid, "typ": "JWT"}).to_string());
+    let payload = general_purpose::URL_SAFE_NO_PAD.encode(claims.to_string());
+    let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+    signer
+        .update(format!("{header}.{payload}").as_bytes())
+        .unwrap();
+    let signature = general_purpose::URL_SAFE_NO_PAD.encode(signer.sign_to_vec().unwrap());
+    format!("{header}.{payload}.{signature}")
+}
+
+fn claims(issuer: &str, groups: &[&str]) -> Value {
+    let now = chrono::Utc::now().timestamp();
+    json!({
+        "exp": now + 300,
+        "iat": now,
+        "jti": "jti",
+        "iss": issuer,
+        "aud": "account",
+        "sub": "sub",
+        "typ": "Bearer",
+        "azp": "quotefault",
+        "scope": "openid",
+        "email_verified": true,
+        "groups": groups,
+        "preferred_username": "cole",
+    })
+}
+
+#[actix_web::test]
+async fn auth_against_local_idp() {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let issuer = format!("http://{}/realms/test", listener.local_addr().unwrap());
+    let server = HttpServer::new({
+        let issuer = issuer.clone();
+        let key = key.clone();
+        move || {
+            App::new()
+                .app_data(Data::new(Idp {
+                    issuer: issuer.clone(),
+                    key: key.clone(),
+                }))
+                .service(openid_configuration)
+                .service(certs)
+        }
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap();
+    env::set_var("QUOTEFAULT_OIDC_ISSUER", &issuer);
+    env::set_var("QUOTEFAULT_OIDC_AUDIENCE", "quotefault");
+    env::remove_var("QUOTEFAULT_OIDC_JWKS_URL");
+    actix_web::rt::spawn(server.run());
+
+    let app = test::init_service(
+        App::new().service(
+            web::scope("/api")
+                .service(whoami)
+                .service(admin)
+                .service(unguarded),
+        ),
+    )
+    .await;
+    let call = |uri: &str, token: Option<String>| {
+        let mut req = test::TestRequest::get().uri(uri);
+        if let Some(token) = token {
+            req = req.insert_header(("Authorization", format!("Bearer {token}")));
+        }
+        req.to_request()
+    };
+
+    let member = sign("test", &claims(&issuer, &["member"]), &key);
+    let res = test::call_service(&app, call("/api/whoami", Some(member.clone()))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(test::read_body(res).await, "cole");
+
+    let res = test::call_service(&app, call("/api/admin", Some(member))).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["code"], "forbidden");
+
+    let rtp = sign("test", &claims(&issuer, &["member", "rtp"]), &key);
+    let res = test::call_service(&app, call("/api/admin", Some(rtp.clone()))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    // Served from the verified token cache
+    let res = test::call_service(&app, call("/api/admin", Some(rtp.clone()))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = test::call_service(&app, call("/api/unguarded", Some(rtp))).await;
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["code"], "internal_error");
+
+    let res = test::call_service(&app, call("/api/whoami", None)).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(body["code"], "unauthorized");
+
+    let mut other_client = claims(&issuer, &["member"]);
+    other_client["azp"] = json!("other");
+    let res = test::call_service(
+        &app,
+        call("/api/whoami", Some(sign("test", &other_client, &key))),
+    )
+    .await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let other_issuer = sign(
+        "test",
+        &claims("http://127.0.0.1:1/realms/test", &["member"]),
+        &key,
+    );
+    let res = test::call_service(&app, call("/api/whoami", Some(other_issuer))).await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let other_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    for kid in ["test", "unknown"] {
+        let forged = sign(kid, &claims(&issuer, &["member"]), &other_key);
+        let res = test::call_service(&app, call("/api/whoami", Some(forged))).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,74 @@
+use std::env;
+
+use actix_web::{get, http::StatusCode, test, web, App, HttpResponse, Responder};
+use quotefault_backend::{
+    api::dev::{get_dev_jwks, mint_dev_token},
+    auth::{CSHAuth, User},
+};
+use serde_json::{json, Value};
+
+#[get("/whoami", wrap = "CSHAuth::enabled()")]
+async fn whoami(user: User) -> impl Responder {
+    HttpResponse::Ok().body(user.preferred_username)
+}
+
+#[get("/admin", wrap = "CSHAuth::admin_only()")]
+async fn admin(user: User) -> impl Responder {
+    HttpResponse::Ok().body(user.preferred_username)
+}
+
+#[actix_web::test]
+async fn dev_tokens_carry_groups() {
+    env::set_var("QUOTEFAULT_DEV_AUTH", "true");
+    env::set_var("SECURITY_ENABLED", "false");
+
+    let app = test::init_service(
+        App::new().service(
+            web::scope("/api")
+                .service(get_dev_jwks)
+                .service(mint_dev_token)
+                .service(whoami)
+                .service(admin),
+        ),
+    )
+    .await;
+
+    let jwks: Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get().uri("/api/dev/jwks").to_request(),
+    )
+    .await;
+    assert_eq!(jwks["keys"][0]["kid"], "quotefault-dev");
+
+    let mint = |username: &str, groups: Value| {
+        test::TestRequest::post()
+            .uri("/api/dev/token")
+            .set_json(json!({ "username": username, "groups": groups }))
+            .to_request()
+    };
+    let get = |uri: &str, token: &Value| {
+        test::TestRequest::get()
+            .uri(uri)
+            .insert_header((
+                "Authorization",
+                format!("Bearer {}", token["token"].as_str().unwrap()),
+            ))
+            .to_request()
+    };
+
+    let member: Value = test::call_and_read_body_json(&app, mint("cole", json!(["member"]))).await;
+    let res = test::call_service(&app, get("/api/whoami", &member)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(test::read_body(res).await, "cole");
+    let res = test::call_service(&app, get("/api/admin", &member)).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let rtp: Value =
+        test::call_and_read_body_json(&app, mint("mcdade", json!(["member", "rtp"]))).await;
+    let res = test::call_service(&app, get("/api/admin", &rtp)).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(test::read_body(res).await, "mcdade");
+
+    let res = test::call_service(&app, mint("", json!([]))).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
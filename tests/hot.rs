@@ -0,0 +1,105 @@
+use std::env;
+
+use quotefault_backend::schema::db::{QuoteShard, Vote};
+use sqlx::{postgres::PgPoolOptions, query, query_file_as, query_scalar};
+
+const SUBMITTER: &str = "hot-test";
+
+/// Votes on quotes as (week, net votes) cast that many weeks after 2025-01-06,
+/// in the order `sort=hot` should list them. A vote counts half as much as one
+/// cast a week later, whenever the quote was submitted.
+const QUOTES: &[&[(i32, i32)]] = &[
+    &[(5, 3)],
+    &[(5, 1)],
+    &[(0, 20)],
+    &[(3, 2), (0, -1)],
+    &[],
+    &[(1, 1), (4, -1)],
+    &[(4, -1)],
+];
+
+#[actix_web::test]
+async fn hot_weighs_votes_by_when_they_were_cast() {
+    // The migrated database the query macros are checked against
+    let Ok(url) = env::var("DATABASE_URL") else {
+        println!("DATABASE_URL not set, skipping");
+        return;
+    };
+    let db = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .expect("Could not connect to database");
+
+    // Rolled back when dropped
+    let mut transaction = db.begin().await.unwrap();
+    let mut expected = Vec::new();
+    for votes in QUOTES {
+        let id: i32 = query_scalar(
+            "INSERT INTO quotes (submitter, timestamp) VALUES ($1, '2025-01-06') RETURNING id",
+        )
+        .bind(SUBMITTER)
+        .fetch_one(&mut *transaction)
+        .await
+        .unwrap();
+        query(
+            "INSERT INTO shards (quote_id, index, body, speaker) VALUES ($1, 1, 'hot', 'someone')",
+        )
+        .bind(id)
+        .execute(&mut *transaction)
+        .await
+        .unwrap();
+        for (week, net) in *votes {
+            query(
+                "INSERT INTO votes (quote_id, vote, submitter, timestamp)
+                SELECT
+                    $1,
+                    (CASE WHEN $3 < 0 THEN 'downvote' ELSE 'upvote' END)::vote,
+                    'voter' || $2 || '-' || i,
+                    '2025-01-06'::timestamp + make_interval(weeks => $2)
+                FROM generate_series(1, abs($3)) AS i",
+            )
+            .bind(id)
+            .bind(week)
+            .bind(net)
+            .execute(&mut *transaction)
+            .await
+            .unwrap();
+        }
+        expected.push(id);
+    }
+
+    let ids: Vec<i32> = query_file_as!(
+        QuoteShard,
+        "queries/get_quotes.sql",
+        100_i64,                       // $1
+        0_i32,                         // $2
+        None::<String>,                // $3
+        &Vec::<String>::new(),         // $4
+        &vec![SUBMITTER.to_string()],  // $5
+        false,                         // $6
+        false,                         // $7
+        SUBMITTER,                     // $8
+        false,                         // $9
+        &Vec::<String>::new(),         // $10
+        false,                         // $11
+        "hot",                         // $12
+        false,                         // $13
+        None::<String>,                // $14
+        None::<i32>,                   // $15
+        false,                         // $16
+        None::<String>,                // $17
+        None::<chrono::NaiveDateTime>, // $18
+        None::<chrono::NaiveDateTime>, // $19
+        None::<chrono::NaiveDateTime>, // $20
+        None::<chrono::NaiveDateTime>, // $21
+        false,                         // $22
+    )
+    .fetch_all(&mut *transaction)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|shard| shard.id)
+    .collect();
+    assert_eq!(ids, expected);
+}
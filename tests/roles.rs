@@ -0,0 +1,62 @@
+use std::env;
+
+use actix_web::{http::StatusCode, test, web, App};
+use quotefault_backend::api::{
+    dev::mint_dev_token,
+    endpoints::{approve_quote, reject_quote},
+    settings::update_settings,
+    tokens::{create_api_token, revoke_api_token},
+};
+use serde_json::{json, Value};
+
+/// Viewers, including users of `read` API tokens, which are capped at viewer,
+/// are turned away before any mutating handler runs
+#[actix_web::test]
+async fn viewers_cannot_mutate() {
+    env::set_var("QUOTEFAULT_DEV_AUTH", "true");
+    env::set_var("SECURITY_ENABLED", "false");
+    env::set_var("QUOTEFAULT_ROLE_MEMBER", "member");
+
+    let app = test::init_service(
+        App::new().service(
+            web::scope("/api")
+                .service(mint_dev_token)
+                .service(create_api_token)
+                .service(revoke_api_token)
+                .service(update_settings)
+                .service(approve_quote)
+                .service(reject_quote),
+        ),
+    )
+    .await;
+
+    let viewer: Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/dev/token")
+            .set_json(json!({ "username": "cole", "groups": [] }))
+            .to_request(),
+    )
+    .await;
+    let bearer = format!("Bearer {}", viewer["token"].as_str().unwrap());
+
+    let requests = [
+        test::TestRequest::post()
+            .uri("/api/tokens")
+            .set_json(json!({ "name": "cli", "scope": "read", "expires": "2099-01-01T00:00:00" })),
+        test::TestRequest::delete().uri("/api/tokens/1"),
+        test::TestRequest::put()
+            .uri("/api/settings")
+            .set_json(json!({ "require_approval": true, "opt_out": false })),
+        test::TestRequest::post().uri("/api/quote/1/approve"),
+        test::TestRequest::post().uri("/api/quote/1/reject"),
+    ];
+    for request in requests {
+        let req = request
+            .insert_header(("Authorization", bearer.as_str()))
+            .to_request();
+        let method = req.method().clone();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN, "{method}");
+    }
+}